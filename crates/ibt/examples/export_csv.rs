@@ -0,0 +1,41 @@
+use std::{fs::File, io::BufWriter};
+
+use ibt::{
+    IbtFile,
+    csv::{CsvExporter, CsvOptions, ValueFormat},
+};
+
+const USAGE: &str = "Usage: export_csv <PATH_TO_IBT> <OUTPUT_PATH> [--raw] [--laps] [VAR...]";
+
+fn main() {
+    let file_name = std::env::args().nth(1).expect(USAGE);
+    let output_name = std::env::args().nth(2).expect(USAGE);
+    let (flags, vars): (Vec<_>, Vec<_>) =
+        std::env::args().skip(3).partition(|a| a.starts_with("--"));
+
+    let options = CsvOptions {
+        vars: (!vars.is_empty()).then_some(vars),
+        values: if flags.iter().any(|f| f == "--raw") {
+            ValueFormat::Raw
+        } else {
+            ValueFormat::Names
+        },
+        precision: Some(4),
+        ..CsvOptions::default()
+    };
+
+    let file = IbtFile::from_file(&file_name).expect("could not open IBT file");
+    let exporter = CsvExporter::new(&file, options).expect("invalid var selection");
+    if flags.iter().any(|f| f == "--laps") {
+        exporter
+            .write_laps(|lap| {
+                File::create(format!("{output_name}.lap{lap}.csv")).map(BufWriter::new)
+            })
+            .expect("could not write CSV files");
+    } else {
+        let output = File::create(&output_name).expect("could not create output file");
+        exporter
+            .write(BufWriter::new(output))
+            .expect("could not write CSV file");
+    }
+}
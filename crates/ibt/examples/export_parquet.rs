@@ -0,0 +1,21 @@
+use std::{fs::File, io::BufWriter};
+
+use ibt::{IbtFile, parquet::ParquetOptions};
+
+const USAGE: &str = "Usage: export_parquet <PATH_TO_IBT> <OUTPUT_PATH> [VAR...]";
+
+fn main() {
+    let file_name = std::env::args().nth(1).expect(USAGE);
+    let output_name = std::env::args().nth(2).expect(USAGE);
+    let vars: Vec<_> = std::env::args().skip(3).collect();
+
+    let options = ParquetOptions {
+        vars: (!vars.is_empty()).then_some(vars),
+        ..ParquetOptions::default()
+    };
+
+    let file = IbtFile::from_file(&file_name).expect("could not open IBT file");
+    let output = File::create(&output_name).expect("could not create output file");
+    file.write_parquet(BufWriter::new(output), &options)
+        .expect("could not write Parquet file");
+}
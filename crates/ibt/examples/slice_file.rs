@@ -0,0 +1,32 @@
+use std::{fs::File, io::BufWriter};
+
+use ibt::{IbtFile, slice::Selection};
+
+const USAGE: &str =
+    "Usage: slice_file <PATH_TO_IBT> <OUTPUT_PATH> <samples|time|laps> <START> <END>";
+
+fn main() {
+    let file_name = std::env::args().nth(1).expect(USAGE);
+    let output_name = std::env::args().nth(2).expect(USAGE);
+    let kind = std::env::args().nth(3).expect(USAGE);
+    let start = std::env::args().nth(4).expect(USAGE);
+    let end = std::env::args().nth(5).expect(USAGE);
+
+    let selection = match kind.as_str() {
+        "samples" => Selection::Samples(
+            start.parse().expect("invalid start sample")..end.parse().expect("invalid end sample"),
+        ),
+        "time" => Selection::SessionTime(
+            start.parse().expect("invalid start time")..end.parse().expect("invalid end time"),
+        ),
+        "laps" => Selection::Laps(
+            start.parse().expect("invalid start lap")..=end.parse().expect("invalid end lap"),
+        ),
+        _ => panic!("{USAGE}"),
+    };
+
+    let file = IbtFile::from_file(&file_name).expect("could not open IBT file");
+    let output = File::create(&output_name).expect("could not create output file");
+    file.write_slice(&selection, BufWriter::new(output))
+        .expect("could not write sliced file");
+}
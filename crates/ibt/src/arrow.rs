@@ -0,0 +1,249 @@
+//! Conversion of telemetry into [Apache Arrow][arrow] record batches
+//!
+//! Each var becomes one column named after [`VarHeader::name`], with its unit, description and
+//! IBT type stored as field metadata:
+//!
+//! | [`VarType`]  | Arrow type |
+//! |--------------|------------|
+//! | `Char`       | `UInt8`    |
+//! | `Bool`       | `Boolean`  |
+//! | `Int`        | `Int32`    |
+//! | `Bitfield`   | `UInt32`   |
+//! | `Float`      | `Float32`  |
+//! | `Double`     | `Float64`  |
+//!
+//! [`Enum`][crate::telemetry::enums::Enum] and [`Bitfield`][crate::telemetry::bitfields::Bitfield]
+//! vars keep their raw integer values, their meaning can be recovered from the `unit` metadata.
+//! Array vars become fixed-size lists of the above.
+//!
+//! [arrow]: https://arrow.apache.org
+
+use std::{collections::HashMap, sync::Arc};
+
+use arrow_array::{
+    ArrayRef, BooleanArray, FixedSizeListArray, Float32Array, Float64Array, Int32Array,
+    RecordBatch, RecordBatchOptions, UInt8Array, UInt32Array,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+
+use crate::{
+    IbtFile,
+    columns::{Column, ColumnData, Columns},
+    telemetry::{TypedReadError, VarHeader, VarType},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ToArrowError {
+    #[error(transparent)]
+    UnknownVar(#[from] TypedReadError),
+
+    #[error(transparent)]
+    Arrow(#[from] ArrowError),
+}
+
+/// Arrow field for a var
+pub fn field(var: &VarHeader) -> Field {
+    let item = item_type(var.ty);
+    let data_type = if var.count > 1 {
+        DataType::FixedSizeList(
+            Arc::new(Field::new("item", item, false)),
+            list_size(var.count),
+        )
+    } else {
+        item
+    };
+
+    let metadata = HashMap::from([
+        ("unit".to_string(), var.unit.clone()),
+        ("description".to_string(), var.description.clone()),
+        ("ibt_type".to_string(), format!("{:?}", var.ty)),
+    ]);
+    Field::new(&var.name, data_type, false).with_metadata(metadata)
+}
+
+/// Arrow schema for a set of vars
+pub fn schema<'var>(vars: impl IntoIterator<Item = &'var VarHeader>) -> Schema {
+    Schema::new(vars.into_iter().map(field).collect::<Vec<_>>())
+}
+
+fn item_type(ty: VarType) -> DataType {
+    match ty {
+        VarType::Char => DataType::UInt8,
+        VarType::Bool => DataType::Boolean,
+        VarType::Int => DataType::Int32,
+        VarType::Bitfield => DataType::UInt32,
+        VarType::Float => DataType::Float32,
+        VarType::Double => DataType::Float64,
+    }
+}
+
+fn list_size(count: usize) -> i32 {
+    // var counts come from a `c_int`, so this can't fail for parsed headers
+    i32::try_from(count).unwrap_or(i32::MAX)
+}
+
+impl Column {
+    /// Convert the column's values into an Arrow array
+    ///
+    /// Numeric values are moved into the array without copying them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the number of values isn't a multiple of the var's count.
+    pub fn into_arrow(self) -> Result<ArrayRef, ArrowError> {
+        let values: ArrayRef = match self.data {
+            ColumnData::Char(v) => {
+                Arc::new(UInt8Array::from_iter_values(v.into_iter().map(|c| c as u8)))
+            }
+            ColumnData::Bool(v) => Arc::new(BooleanArray::from(v)),
+            ColumnData::Int(v) => Arc::new(Int32Array::from(v)),
+            ColumnData::Bitfield(v) => Arc::new(UInt32Array::from(v)),
+            ColumnData::Float(v) => Arc::new(Float32Array::from(v)),
+            ColumnData::Double(v) => Arc::new(Float64Array::from(v)),
+        };
+        if self.var.count <= 1 {
+            return Ok(values);
+        }
+
+        let item = Arc::new(Field::new("item", item_type(self.var.ty), false));
+        let list = FixedSizeListArray::try_new(item, list_size(self.var.count), values, None)?;
+        Ok(Arc::new(list))
+    }
+}
+
+impl Columns {
+    /// Convert the columns into a single record batch
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the columns have different numbers of samples.
+    pub fn into_record_batch(self) -> Result<RecordBatch, ArrowError> {
+        let schema = schema(self.columns.iter().map(|c| &c.var));
+        let arrays = self
+            .columns
+            .into_iter()
+            .map(Column::into_arrow)
+            .collect::<Result<_, _>>()?;
+        let options = RecordBatchOptions::new().with_row_count(Some(self.sample_count));
+        RecordBatch::try_new_with_options(Arc::new(schema), arrays, &options)
+    }
+}
+
+impl IbtFile {
+    /// Arrow schema for all vars in the file
+    pub fn arrow_schema(&self) -> Schema {
+        schema(self.vars.all_vars())
+    }
+
+    /// Convert all vars into a single record batch
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the batch can't be built.
+    pub fn to_record_batch(&self) -> Result<RecordBatch, ToArrowError> {
+        let names: Vec<_> = self.vars.all_vars().map(|v| v.name.as_str()).collect();
+        self.record_batch(&names)
+    }
+
+    /// Convert the given vars into a single record batch
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no var with one of the given names or the batch can't be
+    /// built.
+    pub fn record_batch<S: AsRef<str>>(&self, names: &[S]) -> Result<RecordBatch, ToArrowError> {
+        Ok(self.columns(names)?.into_record_batch()?)
+    }
+
+    /// Convert the given vars into record batches of at most `rows_per_batch` samples
+    ///
+    /// The batches share the memory of one large batch, so this is no more expensive than
+    /// [`IbtFile::record_batch`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no var with one of the given names or the batch can't be
+    /// built.
+    pub fn record_batches<S: AsRef<str>>(
+        &self,
+        names: &[S],
+        rows_per_batch: usize,
+    ) -> Result<Vec<RecordBatch>, ToArrowError> {
+        let batch = self.record_batch(names)?;
+        let rows_per_batch = rows_per_batch.max(1);
+        Ok((0..batch.num_rows())
+            .step_by(rows_per_batch)
+            .map(|start| batch.slice(start, rows_per_batch.min(batch.num_rows() - start)))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{
+        Array, FixedSizeListArray, Float32Array, Int32Array, RecordBatch, cast::AsArray,
+    };
+    use arrow_schema::DataType;
+    use claims::{assert_matches, assert_ok, assert_some_eq};
+
+    use crate::{
+        IbtFile,
+        arrow::ToArrowError,
+        columns::{Column, ColumnData},
+        raw,
+        telemetry::VarHeader,
+        test_utils::test_ibt,
+    };
+
+    #[test]
+    fn converts_file_to_record_batch() {
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt(&[1.0, 1.5, 2.0])));
+        let batch = assert_ok!(file.to_record_batch());
+
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(batch.num_columns(), 3);
+        let schema = batch.schema();
+        let field = assert_ok!(schema.field_with_name("Speed"));
+        assert_eq!(field.data_type(), &DataType::Float32);
+        assert_some_eq!(field.metadata().get("ibt_type"), "Float");
+
+        let speed = batch.column_by_name("Speed").unwrap();
+        assert_eq!(
+            speed
+                .as_any()
+                .downcast_ref::<Float32Array>()
+                .unwrap()
+                .values(),
+            &[2.0, 3.0, 4.0]
+        );
+
+        let batches = assert_ok!(file.record_batches(&["Lap"], 2));
+        assert_eq!(
+            batches
+                .iter()
+                .map(RecordBatch::num_rows)
+                .collect::<Vec<_>>(),
+            [2, 1]
+        );
+        assert_matches!(
+            file.record_batch(&["RPM"]),
+            Err(ToArrowError::UnknownVar(_))
+        );
+    }
+
+    #[test]
+    fn converts_array_vars_to_lists() {
+        let raw = raw::VarHeader::new(2, 0, 2, 0, b"CarIdxLap", b"", b"");
+        let column = Column {
+            var: assert_ok!(VarHeader::from_raw(&raw)),
+            data: ColumnData::Int(vec![1, 2, 3, 4, 5, 6]),
+        };
+
+        let array = assert_ok!(column.into_arrow());
+        let list: &FixedSizeListArray = array.as_fixed_size_list();
+        assert_eq!(list.len(), 3);
+        let second = list.value(1);
+        let second = second.as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(second.values(), &[3, 4]);
+    }
+}
@@ -0,0 +1,219 @@
+//! Reading whole channels at once
+//!
+//! Samples are stored one after another, so the values of a single var are spread across the
+//! file with a stride of one sample. Gathering them into a contiguous `Vec` makes them much easier
+//! to analyze than reading them sample by sample.
+
+use crate::{
+    IbtFile,
+    telemetry::{TypedArrayVar, TypedReadError, VarHeader, VarType, VarValue},
+};
+
+/// All values of a var, in sample order
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColumnData {
+    Char(Vec<char>),
+    Bool(Vec<bool>),
+    Int(Vec<i32>),
+    Bitfield(Vec<u32>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+}
+
+impl ColumnData {
+    /// Total number of values, which is the number of samples times the var's count
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Char(v) => v.len(),
+            Self::Bool(v) => v.len(),
+            Self::Int(v) => v.len(),
+            Self::Bitfield(v) => v.len(),
+            Self::Float(v) => v.len(),
+            Self::Double(v) => v.len(),
+        }
+    }
+
+    /// Whether the column has no values
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A var and all of its values
+///
+/// Values of array vars are flattened, so each sample takes up `var.count` consecutive values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+    pub var: VarHeader,
+    pub data: ColumnData,
+}
+
+impl Column {
+    /// The values of a `Float` or `Double` column, as `f64`
+    pub(crate) fn to_f64(&self) -> Result<Vec<f64>, TypedReadError> {
+        match &self.data {
+            ColumnData::Float(v) => Ok(v.iter().map(|v| f64::from(*v)).collect()),
+            ColumnData::Double(v) => Ok(v.clone()),
+            _ => Err(TypedReadError::TypeMismatch {
+                name: self.var.name.clone(),
+                ty: self.var.ty,
+                requested: "f64",
+            }),
+        }
+    }
+}
+
+/// Several columns with the same number of samples, see [`IbtFile::columns`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Columns {
+    /// Number of samples in each column
+    pub sample_count: usize,
+    pub columns: Vec<Column>,
+}
+
+impl Columns {
+    /// Get a column by var name
+    pub fn get(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|c| c.var.name == name)
+    }
+}
+
+impl IbtFile {
+    /// Read all values of a scalar var as `T`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no var with the given name, its type doesn't match `T`, or
+    /// it is an array.
+    pub fn column<T: VarValue>(&self, name: &str) -> Result<Vec<T>, TypedReadError> {
+        let var = self.vars.typed::<T>(name)?;
+        Ok(self.gather(var.offset, var.size, 1))
+    }
+
+    /// Like [`IbtFile::column`], but `None` if there is no var with the given name
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the var's type doesn't match `T` or it is an array.
+    pub fn optional_column<T: VarValue>(
+        &self,
+        name: &str,
+    ) -> Result<Option<Vec<T>>, TypedReadError> {
+        match self.vars.var(name) {
+            Some(_) => self.column(name).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Read all values of an array var as `T`, flattened so each sample takes up
+    /// [`TypedArrayVar::count`] consecutive values
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no var with the given name or its type doesn't match `T`.
+    pub fn array_column<T: VarValue>(&self, name: &str) -> Result<Vec<T>, TypedReadError> {
+        let var: TypedArrayVar<T> = self.vars.typed_array(name)?;
+        Ok(self.gather(var.offset, var.size, var.count))
+    }
+
+    /// Read all values of several vars
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TypedReadError::UnknownVar`] if there is no var with one of the given names.
+    pub fn columns<S: AsRef<str>>(&self, names: &[S]) -> Result<Columns, TypedReadError> {
+        let columns = names
+            .iter()
+            .map(|name| {
+                let name = name.as_ref();
+                let var = self
+                    .vars
+                    .var(name)
+                    .ok_or_else(|| TypedReadError::UnknownVar(name.to_string()))?;
+                Ok(self.read_column(var))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Columns {
+            sample_count: self.sample_count(),
+            columns,
+        })
+    }
+
+    /// Read all values of a var
+    pub fn read_column(&self, var: &VarHeader) -> Column {
+        let (offset, size, count) = (var.offset, var.ty.size(), var.count);
+        let data = match var.ty {
+            VarType::Char => ColumnData::Char(self.gather(offset, size, count)),
+            VarType::Bool => ColumnData::Bool(self.gather(offset, size, count)),
+            VarType::Int => ColumnData::Int(self.gather(offset, size, count)),
+            VarType::Bitfield => ColumnData::Bitfield(self.gather(offset, size, count)),
+            VarType::Float => ColumnData::Float(self.gather(offset, size, count)),
+            VarType::Double => ColumnData::Double(self.gather(offset, size, count)),
+        };
+        Column {
+            var: var.clone(),
+            data,
+        }
+    }
+
+    /// Strided gather of `count` values of `size` bytes at `offset` in every sample
+    fn gather<T: VarValue>(&self, offset: usize, size: usize, count: usize) -> Vec<T> {
+        let sample_len = self.header.buf_len;
+        let start = self.var_buf_info.buf_offset;
+        let samples = &self.data[start..start + sample_len * self.sample_count()];
+
+        let mut values = Vec::with_capacity(self.sample_count() * count);
+        for sample in samples.chunks_exact(sample_len) {
+            let bytes = &sample[offset..offset + size * count];
+            values.extend(bytes.chunks_exact(size).map(T::from_bytes));
+        }
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_matches, assert_ok, assert_ok_eq, assert_some};
+
+    use crate::{
+        IbtFile,
+        columns::ColumnData,
+        telemetry::{TypedReadError, VarType},
+        test_utils::test_ibt,
+    };
+
+    #[test]
+    fn reads_columns() {
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt(&[1.0, 1.5, 2.0])));
+
+        assert_ok_eq!(file.column::<f64>("SessionTime"), [1.0, 1.5, 2.0]);
+        assert_ok_eq!(file.column::<f32>("Speed"), [2.0, 3.0, 4.0]);
+        assert_ok_eq!(file.array_column::<i32>("Lap"), [1, 1, 1]);
+
+        let columns = assert_ok!(file.columns(&["Lap", "Speed"]));
+        assert_eq!(columns.sample_count, 3);
+        assert_eq!(columns.columns[0].var.name, "Lap");
+        assert_eq!(
+            assert_some!(columns.get("Speed")).data,
+            ColumnData::Float(vec![2.0, 3.0, 4.0])
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_or_mismatched_vars() {
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt(&[1.0])));
+
+        assert_matches!(
+            file.column::<f32>("SessionTime"),
+            Err(TypedReadError::TypeMismatch {
+                ty: VarType::Double,
+                ..
+            })
+        );
+        assert_matches!(
+            file.columns(&["Speed", "RPM"]),
+            Err(TypedReadError::UnknownVar(name)) if name == "RPM"
+        );
+    }
+}
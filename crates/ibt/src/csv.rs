@@ -0,0 +1,378 @@
+//! Export of samples to CSV
+//!
+//! Each sample becomes one row. Array vars are expanded into one column per value, named
+//! `Name[0]`, `Name[1]`, and so on.
+
+use std::{fmt::Display, io, ops::Range};
+
+use crate::{
+    IbtFile, IbtFileError,
+    telemetry::{
+        Sample, TypedArrayVar, TypedReadError, Value, VarHeader, VarType, bitfields::Bitfield,
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum CsvExportError {
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error(transparent)]
+    File(#[from] IbtFileError),
+
+    #[error(transparent)]
+    Var(#[from] TypedReadError),
+}
+
+/// Which time column to add before the selected vars
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeColumn {
+    /// No time column
+    None,
+    /// `SessionTime` of each sample, in seconds
+    #[default]
+    SessionTime,
+    /// Seconds since the first exported sample, so each lap starts at 0 when splitting by lap
+    Elapsed,
+}
+
+/// How to render [`Enum`][crate::telemetry::enums::Enum] and
+/// [`Bitfield`][crate::telemetry::bitfields::Bitfield] values
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ValueFormat {
+    /// Variant names for enums and `|`-separated flag names for bitfields
+    #[default]
+    Names,
+    /// The raw integers
+    Raw,
+}
+
+/// Options for a [`CsvExporter`]
+#[derive(Clone, Debug, Default)]
+pub struct CsvOptions {
+    /// Names of the vars to export, or `None` for all vars
+    pub vars: Option<Vec<String>>,
+    pub time_column: TimeColumn,
+    pub values: ValueFormat,
+    /// Number of decimal places for `Float` and `Double` values, or `None` for as many as needed
+    pub precision: Option<usize>,
+    pub delimiter: Option<u8>,
+}
+
+/// A selected var and how to read it
+#[derive(Clone, Debug)]
+enum CsvColumn<'file> {
+    Decoded(&'file VarHeader),
+    RawInt(TypedArrayVar<i32>),
+    RawBitfield(TypedArrayVar<u32>),
+}
+
+/// Writes samples of a file to CSV
+#[derive(Clone, Debug)]
+pub struct CsvExporter<'file> {
+    file: &'file IbtFile,
+    options: CsvOptions,
+    headers: Vec<String>,
+    columns: Vec<CsvColumn<'file>>,
+}
+
+impl<'file> CsvExporter<'file> {
+    /// Resolve the selected vars
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a selected var doesn't exist.
+    pub fn new(file: &'file IbtFile, options: CsvOptions) -> Result<Self, CsvExportError> {
+        let vars: Vec<_> = match &options.vars {
+            Some(names) => names
+                .iter()
+                .map(|name| {
+                    file.vars
+                        .var(name)
+                        .ok_or_else(|| TypedReadError::UnknownVar(name.clone()))
+                })
+                .collect::<Result<_, _>>()?,
+            None => file.vars.all_vars().collect(),
+        };
+
+        let mut headers = Vec::new();
+        if options.time_column != TimeColumn::None {
+            headers.push("Time".to_string());
+        }
+        for var in &vars {
+            if var.count > 1 {
+                headers.extend((0..var.count).map(|idx| format!("{}[{idx}]", var.name)));
+            } else {
+                headers.push(var.name.clone());
+            }
+        }
+
+        let columns = vars
+            .into_iter()
+            .map(|var| match (options.values, var.ty) {
+                (ValueFormat::Raw, VarType::Int) => TypedArrayVar::new(var).map(CsvColumn::RawInt),
+                (ValueFormat::Raw, VarType::Bitfield) => {
+                    TypedArrayVar::new(var).map(CsvColumn::RawBitfield)
+                }
+                _ => Ok(CsvColumn::Decoded(var)),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            file,
+            options,
+            headers,
+            columns,
+        })
+    }
+
+    /// Names of the CSV columns
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
+
+    /// Write all samples
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails or a time column is requested for a file without
+    /// `SessionTime`.
+    pub fn write<W: io::Write>(&self, out: W) -> Result<W, CsvExportError> {
+        self.write_range(0..self.file.sample_count(), out)
+    }
+
+    /// Write the samples with the given indices
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails, the samples are out of range, or a time column is
+    /// requested for a file without `SessionTime`.
+    pub fn write_range<W: io::Write>(
+        &self,
+        samples: Range<usize>,
+        out: W,
+    ) -> Result<W, CsvExportError> {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(self.options.delimiter.unwrap_or(b','))
+            .from_writer(out);
+        writer.write_record(&self.headers)?;
+
+        let time = match self.options.time_column {
+            TimeColumn::None => None,
+            TimeColumn::SessionTime | TimeColumn::Elapsed => {
+                Some(self.file.vars.typed::<f64>("SessionTime")?)
+            }
+        };
+        let start_time = match (self.options.time_column, time) {
+            (TimeColumn::Elapsed, Some(time)) => self
+                .file
+                .get(samples.start)
+                .map_or(0.0, |sample| sample.read(time)),
+            _ => 0.0,
+        };
+
+        let mut row = Vec::with_capacity(self.headers.len());
+        for idx in samples {
+            let sample = self.file.try_sample(idx)?;
+            row.clear();
+            if let Some(time) = time {
+                row.push(self.float(sample.read(time) - start_time));
+            }
+            for column in &self.columns {
+                self.push_cells(&sample, column, &mut row);
+            }
+            writer.write_record(&row)?;
+        }
+
+        writer
+            .into_inner()
+            .map_err(|err| CsvExportError::Io(err.into_error()))
+    }
+
+    /// Write each lap to its own CSV file
+    ///
+    /// A lap is a run of consecutive samples with the same `Lap` value. `create` is called with
+    /// each lap number to get the writer for that lap.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file has no `Lap` var, `create` fails, or writing fails.
+    pub fn write_laps<W, F>(&self, mut create: F) -> Result<Vec<(i32, W)>, CsvExportError>
+    where
+        W: io::Write,
+        F: FnMut(i32) -> io::Result<W>,
+    {
+        let laps = self.file.column::<i32>("Lap")?;
+
+        let mut written = Vec::new();
+        let mut start = 0;
+        for (end, lap) in laps.iter().enumerate().skip(1) {
+            if *lap != laps[start] {
+                let out = self.write_range(start..end, create(laps[start])?)?;
+                written.push((laps[start], out));
+                start = end;
+            }
+        }
+        if let Some(lap) = laps.get(start) {
+            let out = self.write_range(start..laps.len(), create(*lap)?)?;
+            written.push((*lap, out));
+        }
+        Ok(written)
+    }
+
+    fn push_cells(&self, sample: &Sample<'_>, column: &CsvColumn<'_>, row: &mut Vec<String>) {
+        let var = match column {
+            CsvColumn::RawInt(var) => {
+                row.extend(sample.read_array(*var).iter().map(|v| v.to_string()));
+                return;
+            }
+            CsvColumn::RawBitfield(var) => {
+                row.extend(sample.read_array(*var).iter().map(|v| v.to_string()));
+                return;
+            }
+            CsvColumn::Decoded(var) => var,
+        };
+
+        match sample.read_var(var) {
+            Value::Char(v) => row.push(v.to_string()),
+            Value::Bool(v) => row.push(v.to_string()),
+            Value::Int(v) => row.push(v.to_string()),
+            Value::Bitfield(v) => row.push(bitfield_cell(v)),
+            Value::Float(v) => row.push(self.float(v)),
+            Value::Double(v) => row.push(self.float(v)),
+            Value::Enum(v) => row.push(v.to_string()),
+            Value::CharArray(v) => row.extend(v.iter().map(char::to_string)),
+            Value::BoolArray(v) => row.extend(v.iter().map(bool::to_string)),
+            Value::IntArray(v) => row.extend(v.iter().map(i32::to_string)),
+            Value::BitfieldArray(v) => row.extend(v.iter().map(|b| bitfield_cell(*b))),
+            Value::FloatArray(v) => row.extend(v.iter().map(|v| self.float(*v))),
+            Value::DoubleArray(v) => row.extend(v.iter().map(|v| self.float(*v))),
+            Value::EnumArray(v) => row.extend(v.iter().map(ToString::to_string)),
+        }
+    }
+
+    /// Format a `Float` or `Double`, from its own type so `f32`s print as written
+    fn float<F: Display>(&self, value: F) -> String {
+        match self.options.precision {
+            Some(precision) => format!("{value:.precision$}"),
+            None => value.to_string(),
+        }
+    }
+}
+
+/// Flag names of a bitfield, with bits that have no name as `bitN`, or its raw value if its
+/// type is unknown
+fn bitfield_cell(value: Bitfield) -> String {
+    match value {
+        Bitfield::Unknown(bits) => bits.to_string(),
+        _ => value.flag_names().join("|"),
+    }
+}
+
+impl IbtFile {
+    /// Write samples to CSV, see [`CsvExporter`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a selected var doesn't exist or writing fails.
+    pub fn write_csv<W: io::Write>(
+        &self,
+        options: CsvOptions,
+        out: W,
+    ) -> Result<W, CsvExportError> {
+        CsvExporter::new(self, options)?.write(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_matches, assert_ok};
+
+    use crate::{
+        IbtFile,
+        csv::{CsvExportError, CsvExporter, CsvOptions, TimeColumn},
+        telemetry::TypedReadError,
+        test_utils::{TestValues, test_ibt, test_ibt_with_laps, test_ibt_with_vars},
+    };
+
+    #[test]
+    fn writes_selected_vars() {
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt(&[1.0, 1.5])));
+        let options = CsvOptions {
+            vars: Some(vec!["Speed".to_string(), "Lap".to_string()]),
+            time_column: TimeColumn::Elapsed,
+            precision: Some(2),
+            ..CsvOptions::default()
+        };
+
+        let out = assert_ok!(file.write_csv(options, Vec::new()));
+        assert_eq!(
+            String::from_utf8_lossy(&out),
+            "Time,Speed,Lap\n0.00,2.00,1\n0.50,3.00,1\n"
+        );
+    }
+
+    #[test]
+    fn splits_by_lap() {
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt_with_laps(&[
+            (1.0, 1),
+            (1.5, 1),
+            (2.0, 2)
+        ])));
+        let options = CsvOptions {
+            time_column: TimeColumn::None,
+            ..CsvOptions::default()
+        };
+        let exporter = assert_ok!(CsvExporter::new(&file, options));
+        assert_eq!(exporter.headers(), ["SessionTime", "Speed", "Lap"]);
+
+        let laps = assert_ok!(exporter.write_laps(|_| Ok(Vec::new())));
+        let laps: Vec<_> = laps
+            .into_iter()
+            .map(|(lap, out)| (lap, String::from_utf8_lossy(&out).into_owned()))
+            .collect();
+        assert_eq!(
+            laps,
+            [
+                (1, "SessionTime,Speed,Lap\n1,2,1\n1.5,3,1\n".to_string()),
+                (2, "SessionTime,Speed,Lap\n2,4,2\n".to_string()),
+            ]
+        );
+
+        let options = CsvOptions {
+            vars: Some(vec!["RPM".to_string()]),
+            ..CsvOptions::default()
+        };
+        assert_matches!(
+            CsvExporter::new(&file, options),
+            Err(CsvExportError::Var(TypedReadError::UnknownVar(_)))
+        );
+    }
+
+    #[test]
+    fn writes_exact_floats_and_unnamed_bitfields() {
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt_with_vars(&[
+            ("Speed", "m/s", TestValues::Float(vec![0.1])),
+            ("Mask", "", TestValues::Bitfield(vec![5])),
+            (
+                "SessionFlags",
+                "irsdk_Flags",
+                // green and two bits without a name
+                TestValues::Bitfield(vec![1 << 2 | 1 << 22 | 1 << 23]),
+            ),
+        ])));
+        let options = CsvOptions {
+            time_column: TimeColumn::None,
+            ..CsvOptions::default()
+        };
+
+        let out = assert_ok!(file.write_csv(options, Vec::new()));
+        assert_eq!(
+            String::from_utf8_lossy(&out),
+            "Speed,Mask,SessionFlags\n0.1,5,green|bit22|bit23\n"
+        );
+    }
+}
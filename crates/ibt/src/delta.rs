@@ -0,0 +1,198 @@
+//! Comparing two laps by distance
+//!
+//! Laps are lined up by `LapDistPct` rather than `LapDist`, so laps from files where the track
+//! length came out slightly different still line up at the start/finish line.
+
+use crate::{
+    IbtFile,
+    laps::Lap,
+    resample::{Axis, ResampleError},
+};
+
+/// Time into a lap and speed at fixed steps of `LapDistPct`
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LapTrace {
+    /// Lap distance of each point, from 0 to 1
+    pub pct: Vec<f64>,
+    /// Time since the lap started, in seconds
+    pub time: Vec<f64>,
+    /// `Speed`, in m/s
+    pub speed: Vec<f64>,
+}
+
+/// The difference between two laps along the lap, see [`LapTrace::delta`]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LapDelta {
+    /// Lap distance of each point, from 0 to 1
+    pub pct: Vec<f64>,
+    /// Time the compared lap took to reach the point minus the time the reference lap took, in
+    /// seconds. Positive where the compared lap is behind.
+    pub time: Vec<f64>,
+    /// Speed of the compared lap minus the speed of the reference lap, in m/s
+    pub speed: Vec<f64>,
+}
+
+impl LapDelta {
+    /// The delta at the last point, which is the difference in lap time if both laps are
+    /// complete
+    pub fn final_time(&self) -> Option<f64> {
+        self.time.last().copied()
+    }
+}
+
+impl LapTrace {
+    /// Compare another lap to this one
+    ///
+    /// The delta is computed at each of this trace's points that the other trace covers, with
+    /// the other trace's values interpolated between its points.
+    pub fn delta(&self, compared: &LapTrace) -> LapDelta {
+        let mut delta = LapDelta::default();
+        let mut idx = 0;
+        for ((pct, time), speed) in self.pct.iter().zip(&self.time).zip(&self.speed) {
+            while idx + 1 < compared.pct.len() && compared.pct[idx + 1] < *pct {
+                idx += 1;
+            }
+            let Some(frac) = compared.frac(idx, *pct) else {
+                continue;
+            };
+            let lerp = |values: &[f64]| match values.get(idx + 1) {
+                Some(next) => values[idx] + (next - values[idx]) * frac,
+                None => values[idx],
+            };
+
+            delta.pct.push(*pct);
+            delta.time.push(lerp(&compared.time) - time);
+            delta.speed.push(lerp(&compared.speed) - speed);
+        }
+        delta
+    }
+
+    /// How far `pct` is from point `idx` towards the next one, if it lies between them
+    fn frac(&self, idx: usize, pct: f64) -> Option<f64> {
+        let start = *self.pct.get(idx)?;
+        match self.pct.get(idx + 1) {
+            Some(end) if (start..=*end).contains(&pct) && end > &start => {
+                Some((pct - start) / (end - start))
+            }
+            _ => (pct == start).then_some(0.0),
+        }
+    }
+}
+
+impl IbtFile {
+    /// Resample a lap of this file to a [`LapTrace`], with points every `step` of `LapDistPct`
+    ///
+    /// Complete laps also get points at `0.0` and `1.0`, where they cross the line.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file lacks the `SessionTime`, `Speed` or `LapDistPct` vars, one of
+    /// them isn't a float, or the step is not positive.
+    pub fn lap_trace(&self, lap: &Lap, step: f64) -> Result<LapTrace, ResampleError> {
+        let resampled = self.resample(
+            &["SessionTime", "Speed"],
+            lap.sample_range.clone(),
+            Axis::LapDistPct { step },
+        )?;
+        let columns = &resampled.columns.columns;
+
+        let mut trace = LapTrace {
+            pct: resampled.axis.clone(),
+            time: columns[0]
+                .to_f64()?
+                .iter()
+                .map(|t| t - lap.start_time)
+                .collect(),
+            speed: columns[1].to_f64()?,
+        };
+        if let (Some(lap_time), Some(first), Some(last)) = (
+            lap.lap_time,
+            trace.speed.first().copied(),
+            trace.speed.last().copied(),
+        ) {
+            if trace.pct[0] > 0.0 {
+                trace.pct.insert(0, 0.0);
+                trace.time.insert(0, 0.0);
+                trace.speed.insert(0, first);
+            }
+            trace.pct.push(1.0);
+            trace.time.push(lap_time);
+            trace.speed.push(last);
+        }
+        Ok(trace)
+    }
+
+    /// Compare a lap of another file, or of this one, to a lap of this file
+    ///
+    /// # Errors
+    ///
+    /// See [`IbtFile::lap_trace`].
+    pub fn lap_delta(
+        &self,
+        reference: &Lap,
+        other: &IbtFile,
+        compared: &Lap,
+        step: f64,
+    ) -> Result<LapDelta, ResampleError> {
+        let reference = self.lap_trace(reference, step)?;
+        Ok(reference.delta(&other.lap_trace(compared, step)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_matches, assert_ok, assert_some};
+
+    use crate::{
+        IbtFile,
+        resample::ResampleError,
+        telemetry::TypedReadError,
+        test_utils::{TestValues, test_ibt_with_slow_lap, test_ibt_with_vars},
+    };
+
+    fn test_file() -> IbtFile {
+        IbtFile::from_bytes(&test_ibt_with_slow_lap()).unwrap()
+    }
+
+    #[test]
+    fn computes_delta_between_laps() {
+        let file = test_file();
+        let laps = assert_ok!(file.laps());
+        let delta = assert_ok!(file.lap_delta(&laps[1], &file, &laps[2], 0.05));
+
+        assert_eq!(delta.pct.first(), Some(&0.0));
+        assert_eq!(delta.pct.last(), Some(&1.0));
+        let at = |pct: f64| {
+            let idx = delta
+                .pct
+                .iter()
+                .position(|p| (p - pct).abs() < 1e-6)
+                .unwrap();
+            (delta.time[idx], delta.speed[idx])
+        };
+
+        assert!(at(0.25).0.abs() < 1e-4);
+        assert!(at(0.5).0.abs() < 1e-4);
+        let (time, speed) = at(0.75);
+        assert!((time - 2.0).abs() < 1e-4, "{time}");
+        assert!((speed + 1.0).abs() < 1e-4, "{speed}");
+        assert!((assert_some!(delta.final_time()) - 4.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rejects_speed_of_the_wrong_type() {
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt_with_vars(&[
+            ("SessionTime", "s", TestValues::Double(vec![0.0, 1.0])),
+            ("Lap", "", TestValues::Int(vec![1, 1])),
+            ("LapDistPct", "%", TestValues::Float(vec![0.1, 0.2])),
+            ("Speed", "m/s", TestValues::Int(vec![10, 10])),
+        ])));
+        let lap = &assert_ok!(file.laps())[0];
+        assert_matches!(
+            file.lap_trace(lap, 0.05),
+            Err(ResampleError::Var(TypedReadError::TypeMismatch { .. }))
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! A timeline of what happened to the player's car
+//!
+//! Events are found by scanning vars for changes from one sample to the next. Each kind of
+//! event comes from its own var, and kinds whose var isn't in the file are skipped.
+
+use crate::{
+    IbtFile,
+    runs::runs,
+    telemetry::{
+        TypedReadError,
+        bitfields::{EngineWarnings, Flags},
+        enums::TrackLocation,
+    },
+};
+
+/// Something that happened at a sample of a file
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Event {
+    /// Index of the sample the event happened at
+    pub sample: usize,
+    /// `SessionTime` of the sample, in seconds
+    pub session_time: f64,
+    /// `Lap` of the sample, if the file has it
+    pub lap: Option<i32>,
+    pub kind: EventKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EventKind {
+    /// `OnPitRoad` was set, or was already set at the first sample
+    PitEntry,
+    /// `OnPitRoad` was cleared
+    PitExit,
+    /// The car stopped in its pit stall, from `PlayerCarInPitStall`
+    PitStall {
+        /// Seconds until the car left its stall, or until the end of the file
+        duration: f64,
+    },
+    /// A flag of `SessionFlags` was set
+    ///
+    /// Flags already set at the first sample are reported there.
+    FlagRaised {
+        /// The flag, as the only bit set
+        flag: Flags,
+    },
+    /// A flag of `SessionFlags` was cleared
+    FlagCleared {
+        /// The flag, as the only bit set
+        flag: Flags,
+    },
+    /// `PlayerCarMyIncidentCount` went up
+    Incident {
+        /// Incident points added
+        points: i32,
+        /// Incident points so far
+        total: i32,
+    },
+    /// `PlayerTrackSurface` was `OffTrack`
+    OffTrack {
+        /// Seconds until the car was back on track, or until the end of the file
+        duration: f64,
+    },
+    /// A warning of `EngineWarnings` was set
+    EngineWarning {
+        /// The warning, as the only bit set
+        warning: EngineWarnings,
+    },
+}
+
+impl IbtFile {
+    /// Scan the file for events, in sample order
+    ///
+    /// Spans like pit road visits, pit stalls and off-tracks are reported where they start,
+    /// including those already under way at the first sample.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file has no `SessionTime` var or one of the scanned vars has an
+    /// unexpected type.
+    pub fn events(&self) -> Result<Vec<Event>, TypedReadError> {
+        let times: Vec<f64> = self.column("SessionTime")?;
+        let laps: Option<Vec<i32>> = self.optional_column("Lap")?;
+        let mut kinds: Vec<(usize, EventKind)> = Vec::new();
+
+        if let Some(on_pit_road) = self.optional_column::<bool>("OnPitRoad")? {
+            for run in runs(&on_pit_road) {
+                kinds.push((run.start, EventKind::PitEntry));
+                if run.end < on_pit_road.len() {
+                    kinds.push((run.end, EventKind::PitExit));
+                }
+            }
+        }
+
+        if let Some(in_stall) = self.optional_column::<bool>("PlayerCarInPitStall")? {
+            for run in runs(&in_stall) {
+                let duration = times[run.end.min(times.len() - 1)] - times[run.start];
+                kinds.push((run.start, EventKind::PitStall { duration }));
+            }
+        }
+
+        if let Some(flags) = self.optional_column::<Flags>("SessionFlags")? {
+            let mut previous = 0;
+            for (idx, flags) in flags.iter().enumerate() {
+                let (raised, cleared) = changed_bits(previous, flags.bits());
+                kinds.extend(
+                    Flags::from_bits(raised)
+                        .each_flag()
+                        .map(|flag| (idx, EventKind::FlagRaised { flag })),
+                );
+                kinds.extend(
+                    Flags::from_bits(cleared)
+                        .each_flag()
+                        .map(|flag| (idx, EventKind::FlagCleared { flag })),
+                );
+                previous = flags.bits();
+            }
+        }
+
+        if let Some(incidents) = self.optional_column::<i32>("PlayerCarMyIncidentCount")? {
+            for (idx, pair) in incidents.windows(2).enumerate() {
+                if pair[1] > pair[0] {
+                    let points = pair[1] - pair[0];
+                    let total = pair[1];
+                    kinds.push((idx + 1, EventKind::Incident { points, total }));
+                }
+            }
+        }
+
+        if let Some(surface) = self.optional_column::<TrackLocation>("PlayerTrackSurface")? {
+            let off_track: Vec<bool> = surface
+                .iter()
+                .map(|s| *s == TrackLocation::OffTrack)
+                .collect();
+            for run in runs(&off_track) {
+                let duration = times[run.end.min(times.len() - 1)] - times[run.start];
+                kinds.push((run.start, EventKind::OffTrack { duration }));
+            }
+        }
+
+        if let Some(warnings) = self.optional_column::<EngineWarnings>("EngineWarnings")? {
+            let mut previous = 0;
+            for (idx, warnings) in warnings.iter().enumerate() {
+                let (raised, _) = changed_bits(previous, warnings.bits());
+                kinds.extend(
+                    EngineWarnings::from_bits(raised)
+                        .each_flag()
+                        .map(|warning| (idx, EventKind::EngineWarning { warning })),
+                );
+                previous = warnings.bits();
+            }
+        }
+
+        // sorting is stable, so events at the same sample stay in the order above
+        kinds.sort_by_key(|(idx, _)| *idx);
+        Ok(kinds
+            .into_iter()
+            .map(|(sample, kind)| Event {
+                sample,
+                session_time: times[sample],
+                lap: laps.as_ref().map(|laps| laps[sample]),
+                kind,
+            })
+            .collect())
+    }
+}
+
+/// Bits that were set and bits that were cleared between two values
+fn changed_bits(previous: u32, current: u32) -> (u32, u32) {
+    (current & !previous, previous & !current)
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_ok;
+
+    use crate::{
+        IbtFile,
+        events::EventKind,
+        telemetry::bitfields::{EngineWarnings, Flags},
+        test_utils::{TestValues, test_ibt_with_vars},
+    };
+
+    #[test]
+    fn finds_events() {
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt_with_vars(&[
+            (
+                "SessionTime",
+                "s",
+                TestValues::Double(vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0])
+            ),
+            ("Lap", "", TestValues::Int(vec![1, 1, 1, 2, 2, 2])),
+            (
+                "OnPitRoad",
+                "",
+                TestValues::Bool(vec![false, true, true, true, false, false])
+            ),
+            (
+                "PlayerCarInPitStall",
+                "",
+                TestValues::Bool(vec![false, false, true, false, false, false])
+            ),
+            (
+                "SessionFlags",
+                "irsdk_Flags",
+                // green, then green and yellow, then yellow
+                TestValues::Bitfield(vec![4, 4, 4, 12, 8, 8])
+            ),
+            (
+                "PlayerCarMyIncidentCount",
+                "",
+                TestValues::Int(vec![0, 0, 0, 0, 2, 2])
+            ),
+            (
+                "PlayerTrackSurface",
+                "irsdk_TrkLoc",
+                // on track, then off track from the fifth sample
+                TestValues::Int(vec![3, 3, 3, 3, 0, 0])
+            ),
+            (
+                "EngineWarnings",
+                "irsdk_EngineWarnings",
+                // pit speed limiter
+                TestValues::Bitfield(vec![0, 16, 16, 16, 0, 0])
+            ),
+        ])));
+
+        let events = assert_ok!(file.events());
+        let kinds: Vec<_> = events.iter().map(|e| (e.sample, &e.kind)).collect();
+        let flag = Flags::from_bits;
+        assert_eq!(
+            kinds,
+            [
+                (0, &EventKind::FlagRaised { flag: flag(1 << 2) }),
+                (1, &EventKind::PitEntry),
+                (
+                    1,
+                    &EventKind::EngineWarning {
+                        warning: EngineWarnings::from_bits(1 << 4)
+                    }
+                ),
+                (2, &EventKind::PitStall { duration: 1.0 }),
+                (3, &EventKind::FlagRaised { flag: flag(1 << 3) }),
+                (4, &EventKind::PitExit),
+                (4, &EventKind::FlagCleared { flag: flag(1 << 2) }),
+                (
+                    4,
+                    &EventKind::Incident {
+                        points: 2,
+                        total: 2
+                    }
+                ),
+                (4, &EventKind::OffTrack { duration: 1.0 }),
+            ]
+        );
+        assert_eq!(events[4].session_time, 3.0);
+        assert_eq!(events[4].lap, Some(2));
+    }
+
+    #[test]
+    fn reports_spans_under_way_at_the_start() {
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt_with_vars(&[
+            ("SessionTime", "s", TestValues::Double(vec![0.0, 1.0, 2.0])),
+            ("OnPitRoad", "", TestValues::Bool(vec![true, true, false])),
+            (
+                "SessionFlags",
+                "irsdk_Flags",
+                // a bit without a name
+                TestValues::Bitfield(vec![1 << 22, 1 << 22, 0])
+            ),
+        ])));
+
+        let events = assert_ok!(file.events());
+        let kinds: Vec<_> = events.iter().map(|e| (e.sample, &e.kind)).collect();
+        let flag = Flags::from_bits(1 << 22);
+        assert_eq!(
+            kinds,
+            [
+                (0, &EventKind::PitEntry),
+                (0, &EventKind::FlagRaised { flag }),
+                (2, &EventKind::PitExit),
+                (2, &EventKind::FlagCleared { flag }),
+            ]
+        );
+    }
+}
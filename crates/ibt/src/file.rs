@@ -1,11 +1,20 @@
-use std::path::Path;
+use std::{
+    fs::File,
+    ops::{Deref, Range},
+    path::Path,
+    sync::Arc,
+};
 
 use aligned_vec::{AVec, ConstAlign};
+use memmap2::Mmap;
 use saphyr::LoadableYamlNode;
 
 use crate::{
     raw,
-    telemetry::{DiskSubHeader, Header, RawConversionError, Sample, VarBufInfo, VarHeader, VarSet},
+    telemetry::{
+        DiskSubHeader, Header, RawConversionError, Sample, VarBufInfo, VarHeader, VarHeaderError,
+        VarSet,
+    },
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -20,6 +29,204 @@ pub enum IbtFileError {
     /// An error occured decoding telemetry data
     #[error(transparent)]
     RawTelem(#[from] raw::RawTelemError),
+
+    /// The file is too short to contain the header and disk sub-header
+    #[error("file is `{len}` bytes long, too short to contain the IBT headers")]
+    TruncatedHeader {
+        /// Length of the file
+        len: usize,
+    },
+
+    /// The header describes samples of zero bytes
+    #[error("sample buffer is empty")]
+    EmptySampleBuffer,
+
+    /// The var headers described by the header lie outside of the file
+    #[error("`{num_vars}` var headers at offset `{offset}` lie outside of the file")]
+    VarHeaderOutOfBounds { offset: i32, num_vars: i32 },
+
+    /// One of the var headers could not be decoded
+    #[error("var header `{index}` is invalid")]
+    InvalidVarHeader {
+        /// Position of the var header in the file
+        index: usize,
+        #[source]
+        source: VarHeaderError,
+    },
+
+    /// A var's values lie outside of the sample buffer
+    #[error("var `{name}` lies outside of the `{buf_len}` byte sample buffer")]
+    VarOutOfBounds { name: String, buf_len: usize },
+
+    /// The session string described by the header lies outside of the file
+    #[error("session string of `{len}` bytes at offset `{offset}` lies outside of the file")]
+    SessionStringOutOfBounds { offset: usize, len: usize },
+
+    /// The samples described by the headers lie outside of the file
+    #[error("`{record_count}` samples at offset `{offset}` lie outside of the file")]
+    SamplesOutOfBounds { offset: usize, record_count: usize },
+
+    /// A sample was requested beyond the number of samples in the file
+    #[error("sample `{idx}` is out of range, there are `{count}` samples")]
+    SampleOutOfRange { idx: usize, count: usize },
+}
+
+/// The fixed-size headers at the start of every IBT file
+pub(crate) struct FileHeaders {
+    pub(crate) raw: raw::Header,
+    pub(crate) header: Header,
+    pub(crate) disk_sub_header: DiskSubHeader,
+    pub(crate) var_buf_info: VarBufInfo,
+}
+
+impl FileHeaders {
+    /// Combined size of the header and disk sub-header
+    pub(crate) const SIZE: usize = raw::HEADER_SIZE + raw::SUB_HEADER_SIZE;
+
+    /// Decode the headers from the start of a file, which must be aligned to [`raw::ALIGNMENT`]
+    pub(crate) fn parse(bytes: &[u8]) -> Result<Self, IbtFileError> {
+        if bytes.len() < Self::SIZE {
+            return Err(IbtFileError::TruncatedHeader { len: bytes.len() });
+        }
+
+        let raw = raw::Header::from_raw_bytes(&bytes[..raw::HEADER_SIZE])?;
+        let header = Header::from_raw(&raw)?;
+        if header.buf_len == 0 {
+            return Err(IbtFileError::EmptySampleBuffer);
+        }
+
+        let raw_sub_header =
+            raw::DiskSubHeader::from_raw_bytes(&bytes[raw::HEADER_SIZE..Self::SIZE]);
+        let disk_sub_header = DiskSubHeader::from_raw(&raw_sub_header)?;
+
+        // IBT files only have one variable buffer
+        let var_buf_info = VarBufInfo::from_raw(&raw.var_bufs[0])?;
+
+        Ok(Self {
+            raw,
+            header,
+            disk_sub_header,
+            var_buf_info,
+        })
+    }
+
+    /// Byte range of the var headers within a file of length `file_len`
+    pub(crate) fn var_headers_range(&self, file_len: usize) -> Result<Range<usize>, IbtFileError> {
+        let offset = usize::try_from(self.raw.var_header_offset).ok();
+        let len = usize::try_from(self.raw.num_vars)
+            .ok()
+            .and_then(|n| n.checked_mul(raw::VAR_HEADER_SIZE));
+
+        checked_range(offset, len, file_len).ok_or(IbtFileError::VarHeaderOutOfBounds {
+            offset: self.raw.var_header_offset,
+            num_vars: self.raw.num_vars,
+        })
+    }
+
+    /// Byte range of the session string within a file of length `file_len`
+    pub(crate) fn session_range(&self, file_len: usize) -> Result<Range<usize>, IbtFileError> {
+        let offset = self.header.session_info_offset;
+        let len = self.header.session_info_len;
+        checked_range(Some(offset), Some(len), file_len)
+            .ok_or(IbtFileError::SessionStringOutOfBounds { offset, len })
+    }
+
+    /// Byte range of `record_count` samples within a file of length `file_len`
+    pub(crate) fn samples_range(
+        &self,
+        record_count: usize,
+        file_len: usize,
+    ) -> Result<Range<usize>, IbtFileError> {
+        let offset = self.var_buf_info.buf_offset;
+        let len = self.header.buf_len.checked_mul(record_count);
+        checked_range(Some(offset), len, file_len).ok_or(IbtFileError::SamplesOutOfBounds {
+            offset,
+            record_count,
+        })
+    }
+}
+
+/// `offset..offset + len`, if it fits within `0..file_len`
+fn checked_range(
+    offset: Option<usize>,
+    len: Option<usize>,
+    file_len: usize,
+) -> Option<Range<usize>> {
+    let offset = offset?;
+    let end = offset.checked_add(len?)?;
+    (end <= file_len).then_some(offset..end)
+}
+
+/// Decode var headers from aligned raw bytes, checking each var lies within a sample of
+/// `buf_len` bytes
+pub(crate) fn parse_var_set(bytes: &[u8], buf_len: usize) -> Result<VarSet, IbtFileError> {
+    // the headers can only be cast in place if their offset in the file is aligned
+    let copy;
+    let bytes = if bytes.as_ptr().align_offset(raw::ALIGNMENT) == 0 {
+        bytes
+    } else {
+        copy = AVec::<u8, ConstAlign<{ raw::ALIGNMENT }>>::from_slice(raw::ALIGNMENT, bytes);
+        &copy[..]
+    };
+
+    let var_headers = raw::VarHeader::slice_from_fraw_bytes(bytes)
+        .iter()
+        .enumerate()
+        .map(|(index, raw)| {
+            let var = VarHeader::from_raw(raw)
+                .map_err(|source| IbtFileError::InvalidVarHeader { index, source })?;
+            if var.offset + var.size() > buf_len {
+                return Err(IbtFileError::VarOutOfBounds {
+                    name: var.name,
+                    buf_len,
+                });
+            }
+            Ok(var)
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(VarSet::new(var_headers))
+}
+
+/// The bytes backing an [`IbtFile`]
+///
+/// Both variants guarantee the start of the data is aligned to [`raw::ALIGNMENT`].
+#[derive(Clone, Debug)]
+pub(crate) enum Storage {
+    /// The entire file, copied into an aligned buffer
+    Owned(AVec<u8, ConstAlign<{ raw::ALIGNMENT }>>),
+    /// A read-only memory map of the file. Maps always start on a page boundary, which satisfies
+    /// the alignment requirement.
+    Mapped(Arc<Mmap>),
+}
+
+impl Storage {
+    /// Copy the bytes into an aligned buffer
+    pub(crate) fn copy_from(bytes: &[u8]) -> Self {
+        Self::Owned(AVec::from_slice(raw::ALIGNMENT, bytes))
+    }
+
+    /// Memory-map the file at the given path
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped.
+    pub(crate) unsafe fn map<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the caller guarantees the file is not modified while mapped
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Self::Mapped(Arc::new(map)))
+    }
+}
+
+impl Deref for Storage {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Storage::Owned(data) => data,
+            Storage::Mapped(map) => map,
+        }
+    }
 }
 
 /// The contents of a `.ibt` file
@@ -37,9 +244,9 @@ pub enum IbtFileError {
 /// ```
 #[derive(Clone, Debug)]
 pub struct IbtFile {
-    /// Currently the entirety of the file. Must be aligned to 16-bytes to safely read multi-byte
-    /// data.
-    data: AVec<u8, ConstAlign<{ crate::raw::ALIGNMENT }>>,
+    /// The entirety of the file, either read into memory or memory-mapped. Must be aligned to
+    /// 16-bytes to safely read multi-byte data.
+    pub(crate) data: Storage,
 
     pub header: Header,
     pub disk_sub_header: DiskSubHeader,
@@ -54,64 +261,337 @@ pub struct IbtFile {
 impl IbtFile {
     /// Open an IBT file at the given path
     ///
+    /// The whole file is read into memory. For large files, consider [`IbtFile::open_mmap`].
+    ///
     /// # Errors
     ///
     /// Returns an error if the data is invalid or an IO error occurs.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, IbtFileError> {
-        let data = AVec::from_slice(raw::ALIGNMENT, &std::fs::read(&path)?);
+        Self::from_bytes(&std::fs::read(&path)?)
+    }
 
-        let raw_header = raw::Header::from_raw_bytes(&data[..raw::HEADER_SIZE])?;
-        let header = Header::from_raw(&raw_header)?;
+    /// Parse an IBT file from bytes already in memory
+    ///
+    /// The bytes are copied into an aligned buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is invalid.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, IbtFileError> {
+        Self::from_storage(Storage::copy_from(bytes))
+    }
 
-        let raw_sub_header = raw::DiskSubHeader::from_raw_bytes(
-            &data[raw::HEADER_SIZE..raw::HEADER_SIZE + raw::SUB_HEADER_SIZE],
-        );
-        let sub_header = DiskSubHeader::from_raw(&raw_sub_header)?;
-
-        let var_headers_offset = raw_header.var_header_offset as usize;
-        let var_headers_len = raw::VAR_HEADER_SIZE * raw_header.num_vars as usize;
-        let vh_slice = &data[var_headers_offset..var_headers_offset + var_headers_len];
-        let var_headers = raw::VarHeader::slice_from_fraw_bytes(vh_slice)
-            .iter()
-            .map(VarHeader::from_raw)
-            .collect();
-        let vars = VarSet::new(var_headers);
+    /// Open an IBT file at the given path by memory-mapping it
+    ///
+    /// Nothing is copied up front; pages are loaded by the OS as samples are read, and
+    /// [`Sample`]s borrow directly from the map. Cloning the returned `IbtFile` shares the map.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is invalid or an IO error occurs.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped, e.g. by iRacing still
+    /// writing to it. Doing so is undefined behavior.
+    pub unsafe fn open_mmap<P: AsRef<Path>>(path: P) -> Result<Self, IbtFileError> {
+        // SAFETY: the caller guarantees the file is not modified while mapped
+        Self::from_storage(unsafe { Storage::map(path)? })
+    }
 
-        let var_buf_info = VarBufInfo::from_raw(&raw_header.var_bufs[0])?;
+    fn from_storage(data: Storage) -> Result<Self, IbtFileError> {
+        let headers = FileHeaders::parse(&data)?;
+        let vh_range = headers.var_headers_range(data.len())?;
+        let vars = parse_var_set(&data[vh_range], headers.header.buf_len)?;
+        headers.session_range(data.len())?;
+        headers.samples_range(headers.disk_sub_header.record_count, data.len())?;
 
         Ok(Self {
             data,
-            header,
-            disk_sub_header: sub_header,
+            header: headers.header,
+            disk_sub_header: headers.disk_sub_header,
             vars,
-            var_buf_info,
+            var_buf_info: headers.var_buf_info,
         })
     }
 
-    /// Decode the session string as a plain String
-    pub fn raw_session_data(&self) -> String {
+    /// Whether this file is backed by a memory map rather than an in-memory copy
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.data, Storage::Mapped(_))
+    }
+
+    /// The undecoded bytes of the session string
+    pub fn session_bytes(&self) -> &[u8] {
         let offset = self.header.session_info_offset;
         let len = self.header.session_info_len;
-        let session_string = &self.data[offset..offset + len];
-        String::from_utf8_lossy(session_string).into_owned()
+        &self.data[offset..offset + len]
+    }
+
+    /// Decode the session string as a plain String
+    pub fn raw_session_data(&self) -> String {
+        String::from_utf8_lossy(self.session_bytes()).into_owned()
+    }
+
+    /// The disk sub-header in its raw form, preserving the exact start and end times stored in
+    /// the file
+    ///
+    /// The lap and record counts are taken from [`IbtFile::disk_sub_header`], so they reflect any
+    /// repairs made when recovering the file.
+    pub fn raw_disk_sub_header(&self) -> raw::DiskSubHeader {
+        let raw =
+            raw::DiskSubHeader::from_raw_bytes(&self.data[raw::HEADER_SIZE..FileHeaders::SIZE]);
+        raw::DiskSubHeader {
+            lap_count: self
+                .disk_sub_header
+                .lap_count
+                .try_into()
+                .unwrap_or(raw.lap_count),
+            record_count: self.sample_count().try_into().unwrap_or(raw.record_count),
+            ..raw
+        }
     }
 
     /// Parse the session string as YAML
+    ///
+    /// Values iRacing wrote without the quotes YAML needs are quoted first, see
+    /// [`session::sanitize`](crate::session::sanitize).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session string isn't valid YAML or is empty.
     pub fn session_data(&self) -> Result<saphyr::YamlOwned, saphyr::ScanError> {
-        let docs = saphyr::YamlOwned::load_from_str(&self.raw_session_data())?;
-        Ok(docs[0].clone())
+        let session = self.raw_session_data();
+        let docs = saphyr::YamlOwned::load_from_str(&crate::session::sanitize(&session).0)?;
+        docs.into_iter().next().ok_or_else(|| {
+            saphyr::ScanError::new_str(saphyr::Marker::new(0, 1, 0), "session string is empty")
+        })
+    }
+
+    /// Number of samples in the file
+    pub fn sample_count(&self) -> usize {
+        self.disk_sub_header.record_count
     }
 
     /// Retrive the nth sample
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of range. See [`IbtFile::get`] and [`IbtFile::try_sample`] for
+    /// non-panicking alternatives.
     pub fn sample(&self, idx: usize) -> Sample<'_> {
-        assert!(idx < self.disk_sub_header.record_count);
+        self.get(idx).unwrap_or_else(|| {
+            panic!(
+                "sample `{idx}` is out of range, there are `{}` samples",
+                self.sample_count()
+            )
+        })
+    }
+
+    /// Retrive the nth sample, or `None` if it is out of range
+    pub fn get(&self, idx: usize) -> Option<Sample<'_>> {
+        if idx >= self.sample_count() {
+            return None;
+        }
         let sample_len = self.header.buf_len;
         let offset = self.var_buf_info.buf_offset + sample_len * idx;
-        Sample::new(&self.data[offset..offset + sample_len])
+        Some(Sample::new(&self.data[offset..offset + sample_len]))
+    }
+
+    /// Retrive the nth sample
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IbtFileError::SampleOutOfRange`] if `idx` is out of range.
+    pub fn try_sample(&self, idx: usize) -> Result<Sample<'_>, IbtFileError> {
+        self.get(idx).ok_or(IbtFileError::SampleOutOfRange {
+            idx,
+            count: self.sample_count(),
+        })
     }
 
     /// Iterate over all telemetry samples in the file
     pub fn samples(&self) -> impl Iterator<Item = Sample<'_>> {
-        (0..self.disk_sub_header.record_count).map(|idx| self.sample(idx))
+        (0..self.sample_count()).map(|idx| self.sample(idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_err, assert_matches, assert_none, assert_ok, assert_some};
+
+    use crate::{
+        IbtFile, IbtFileError,
+        telemetry::{Value, VarHeaderError},
+        test_utils::test_ibt,
+    };
+
+    #[test]
+    fn reads_samples_from_bytes() {
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt(&[1.0, 1.5, 2.0])));
+        let var = file.vars.var("SessionTime").unwrap();
+
+        let times: Vec<_> = file
+            .samples()
+            .map(|s| match s.read_var(var) {
+                Value::Double(t) => t,
+                v => panic!("unexpected value {v:?}"),
+            })
+            .collect();
+        assert_eq!(times, [1.0, 1.5, 2.0]);
+        assert!(!file.is_mapped());
+    }
+
+    #[test]
+    fn mapped_file_matches_owned_file() {
+        let bytes = test_ibt(&[1.0, 1.5, 2.0]);
+        let path = std::env::temp_dir().join(format!("ibt-mmap-test-{}.ibt", std::process::id()));
+        std::fs::write(&path, &bytes).unwrap();
+
+        // SAFETY: the file is only written by this test, before it is mapped
+        let mapped = assert_ok!(unsafe { IbtFile::open_mmap(&path) });
+        let owned = assert_ok!(IbtFile::from_bytes(&bytes));
+
+        assert!(mapped.is_mapped());
+        assert_eq!(mapped.header, owned.header);
+        assert_eq!(mapped.disk_sub_header, owned.disk_sub_header);
+        let var = mapped.vars.var("Speed").unwrap();
+        for (m, o) in mapped.samples().zip(owned.samples()) {
+            assert_eq!(
+                format!("{:?}", m.read_var(var)),
+                format!("{:?}", o.read_var(var))
+            );
+        }
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rejects_truncated_header() {
+        let bytes = test_ibt(&[1.0]);
+        assert_matches!(
+            IbtFile::from_bytes(&bytes[..100]),
+            Err(IbtFileError::TruncatedHeader { len: 100 })
+        );
+    }
+
+    #[test]
+    fn rejects_var_headers_out_of_bounds() {
+        let bytes = test_ibt(&[1.0]);
+        assert_matches!(
+            IbtFile::from_bytes(&bytes[..200]),
+            Err(IbtFileError::VarHeaderOutOfBounds {
+                offset: 144,
+                num_vars: 3
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_var_type() {
+        let mut bytes = test_ibt(&[1.0]);
+        // type of the second var header
+        bytes[288..292].copy_from_slice(&42_i32.to_le_bytes());
+        assert_matches!(
+            IbtFile::from_bytes(&bytes),
+            Err(IbtFileError::InvalidVarHeader {
+                index: 1,
+                source: VarHeaderError::InvalidType(42)
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_empty_var_count() {
+        let mut bytes = test_ibt(&[1.0]);
+        // count of the second var header
+        bytes[296..300].copy_from_slice(&0_i32.to_le_bytes());
+        assert_matches!(
+            IbtFile::from_bytes(&bytes),
+            Err(IbtFileError::InvalidVarHeader {
+                index: 1,
+                source: VarHeaderError::InvalidCount(0)
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_empty_sample_buffer() {
+        let mut bytes = test_ibt(&[1.0]);
+        // buf_len of the header
+        bytes[36..40].copy_from_slice(&0_i32.to_le_bytes());
+        assert_matches!(
+            IbtFile::from_bytes(&bytes),
+            Err(IbtFileError::EmptySampleBuffer)
+        );
+    }
+
+    #[test]
+    fn reads_misaligned_var_headers() {
+        let bytes = test_ibt(&[1.0, 1.5]);
+        // shift everything after the headers along by one byte
+        let mut shifted = bytes[..144].to_vec();
+        shifted.push(0);
+        shifted.extend(&bytes[144..]);
+        for field in [20, 28, 52] {
+            let value = i32::from_le_bytes(shifted[field..field + 4].try_into().unwrap());
+            shifted[field..field + 4].copy_from_slice(&(value + 1).to_le_bytes());
+        }
+
+        let file = assert_ok!(IbtFile::from_bytes(&shifted));
+        let aligned = assert_ok!(IbtFile::from_bytes(&bytes));
+        assert!(file.vars.all_vars().eq(aligned.vars.all_vars()));
+        assert_eq!(file.raw_session_data(), aligned.raw_session_data());
+    }
+
+    #[test]
+    fn rejects_invalid_session_times() {
+        let mut bytes = test_ibt(&[1.0]);
+        // start time of the disk sub-header
+        bytes[120..128].copy_from_slice(&(-1.0_f64).to_le_bytes());
+        assert_matches!(
+            IbtFile::from_bytes(&bytes),
+            Err(IbtFileError::RawConversionError(_))
+        );
+
+        let mut bytes = test_ibt(&[1.0]);
+        // end time of the disk sub-header
+        bytes[128..136].copy_from_slice(&f64::NAN.to_le_bytes());
+        assert_matches!(
+            IbtFile::from_bytes(&bytes),
+            Err(IbtFileError::RawConversionError(_))
+        );
+    }
+
+    #[test]
+    fn empty_session_string_is_an_error() {
+        let mut bytes = test_ibt(&[1.0]);
+        // session info length
+        bytes[16..20].copy_from_slice(&0_i32.to_le_bytes());
+        let file = assert_ok!(IbtFile::from_bytes(&bytes));
+        assert_err!(file.session_data());
+    }
+
+    #[test]
+    fn rejects_truncated_samples() {
+        let bytes = test_ibt(&[1.0, 1.5]);
+        assert_matches!(
+            IbtFile::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(IbtFileError::SamplesOutOfBounds {
+                record_count: 2,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn out_of_range_samples_are_errors() {
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt(&[1.0, 1.5])));
+
+        assert_some!(file.get(1));
+        assert_none!(file.get(2));
+        assert_matches!(
+            file.try_sample(2),
+            Err(IbtFileError::SampleOutOfRange { idx: 2, count: 2 })
+        );
     }
 }
@@ -0,0 +1,289 @@
+//! Fuel use per lap and per stint
+//!
+//! Fuel used is measured from `FuelLevel`, split at the lap boundaries of [`IbtFile::laps`].
+//! Refuelling is found where `FuelLevel` rises, and a new stint starts on each lap with fuel
+//! added.
+
+use std::ops::Range;
+
+use crate::{IbtFile, telemetry::TypedReadError};
+
+/// The smallest rise in `FuelLevel` counted as refuelling, in liters
+const MIN_REFUEL: f64 = 0.1;
+
+/// Fuel use of a lap
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LapFuel {
+    /// The lap's [`Lap::number`][crate::laps::Lap::number]
+    pub lap: i32,
+    /// `FuelLevel` at the start of the lap, in liters
+    pub start_level: f64,
+    /// `FuelLevel` at the end of the lap, in liters
+    pub end_level: f64,
+    /// Fuel used during the lap, in liters
+    pub used: f64,
+    /// Fuel added during the lap, in liters
+    pub added: f64,
+    /// Mean of `FuelUsePerHour` over the lap, in the var's unit, if the file has it
+    pub use_per_hour: Option<f64>,
+    /// Whether the lap counts towards averages: it is complete, with no pit visits or fuel
+    /// added
+    pub clean: bool,
+}
+
+/// Fuel added in the pits
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Refuel {
+    /// Index of the sample where `FuelLevel` started to rise
+    pub sample: usize,
+    /// `SessionTime` of the sample, in seconds
+    pub session_time: f64,
+    /// Number of the lap the fuel was added in
+    pub lap: i32,
+    /// Fuel added, in liters
+    pub amount: f64,
+}
+
+/// Laps run between refuelling
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stint {
+    /// Indices of the stint's laps in [`FuelReport::laps`]
+    pub laps: Range<usize>,
+    /// Fuel used over the stint, in liters
+    pub used: f64,
+}
+
+/// Fuel use of a file, see [`IbtFile::fuel_report`]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FuelReport {
+    pub laps: Vec<LapFuel>,
+    pub refuels: Vec<Refuel>,
+}
+
+impl FuelReport {
+    /// For each lap, the mean fuel used over the last `window` clean laps up to and including it
+    pub fn rolling_average(&self, window: usize) -> Vec<Option<f64>> {
+        (0..self.laps.len())
+            .map(|idx| mean_used(self.laps[..=idx].iter().rev(), window))
+            .collect()
+    }
+
+    /// The mean fuel used over the last `window` clean laps, in liters
+    pub fn average(&self, window: usize) -> Option<f64> {
+        mean_used(self.laps.iter().rev(), window)
+    }
+
+    /// How many laps the given fuel lasts at the mean use of the last `window` clean laps
+    pub fn laps_remaining(&self, fuel_level: f64, window: usize) -> Option<f64> {
+        self.average(window)
+            .filter(|used| *used > 0.0)
+            .map(|used| fuel_level / used)
+    }
+
+    /// Split the laps into stints, starting a new stint at each lap with fuel added
+    pub fn stints(&self) -> Vec<Stint> {
+        let mut stints = Vec::new();
+        let mut start = 0;
+        for idx in 1..=self.laps.len() {
+            if idx == self.laps.len() || self.laps[idx].added > 0.0 {
+                stints.push(Stint {
+                    laps: start..idx,
+                    used: self.laps[start..idx].iter().map(|l| l.used).sum(),
+                });
+                start = idx;
+            }
+        }
+        stints
+    }
+}
+
+fn mean_used<'a>(laps: impl Iterator<Item = &'a LapFuel>, window: usize) -> Option<f64> {
+    let used: Vec<f64> = laps
+        .filter(|l| l.clean)
+        .take(window)
+        .map(|l| l.used)
+        .collect();
+    (!used.is_empty()).then(|| used.iter().sum::<f64>() / used.len() as f64)
+}
+
+impl IbtFile {
+    /// Measure fuel use per lap and find refuelling
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file has no `FuelLevel` var or lacks a var needed by
+    /// [`IbtFile::laps`].
+    pub fn fuel_report(&self) -> Result<FuelReport, TypedReadError> {
+        let times: Vec<f64> = self.column("SessionTime")?;
+        let level: Vec<f64> = self
+            .column::<f32>("FuelLevel")?
+            .into_iter()
+            .map(f64::from)
+            .collect();
+        let use_per_hour: Option<Vec<f32>> = self.optional_column("FuelUsePerHour")?;
+        let laps = self.laps()?;
+
+        let lap_at = |sample: usize| {
+            laps.iter()
+                .find(|l| l.sample_range.contains(&sample))
+                .map_or(0, |l| l.number)
+        };
+        let refuels: Vec<Refuel> = find_refuels(&level)
+            .into_iter()
+            .map(|(sample, amount)| Refuel {
+                sample,
+                session_time: times[sample],
+                lap: lap_at(sample),
+                amount,
+            })
+            .collect();
+
+        let laps = laps
+            .iter()
+            .map(|lap| {
+                let range = &lap.sample_range;
+                debug_assert!(!range.is_empty(), "laps always have samples");
+                let start_level = level[range.start.saturating_sub(1)];
+                let end_level = level[range.end - 1];
+                let added: f64 = refuels
+                    .iter()
+                    .filter(|r| range.contains(&r.sample))
+                    .map(|r| r.amount)
+                    .sum();
+                let use_per_hour = use_per_hour.as_ref().map(|values| {
+                    let values = &values[range.clone()];
+                    values.iter().map(|v| f64::from(*v)).sum::<f64>() / values.len() as f64
+                });
+
+                LapFuel {
+                    lap: lap.number,
+                    start_level,
+                    end_level,
+                    used: start_level + added - end_level,
+                    added,
+                    use_per_hour,
+                    clean: lap.is_complete() && !lap.pit && added == 0.0,
+                }
+            })
+            .collect();
+
+        Ok(FuelReport { laps, refuels })
+    }
+}
+
+/// `(sample, amount)` of each rise in fuel level, where `sample` is the first sample of the rise
+fn find_refuels(level: &[f64]) -> Vec<(usize, f64)> {
+    let mut refuels = Vec::new();
+    let mut idx = 1;
+    while idx < level.len() {
+        if level[idx] <= level[idx - 1] {
+            idx += 1;
+            continue;
+        }
+        // the level holds or rises until the car drives off
+        let mut end = idx;
+        while end + 1 < level.len() && level[end + 1] >= level[end] {
+            end += 1;
+        }
+        let amount = level[end] - level[idx - 1];
+        if amount >= MIN_REFUEL {
+            refuels.push((idx, amount));
+        }
+        idx = end + 1;
+    }
+    refuels
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_ok, assert_some};
+
+    use crate::{
+        IbtFile,
+        test_utils::{TestValues, test_ibt_with_vars},
+    };
+
+    /// Laps of 10 samples using 2.5 l each, with 20 l added during the fourth lap
+    fn test_file() -> IbtFile {
+        let count = 41;
+        let dist: Vec<i32> = (5..5 + count).collect();
+        let mut fuel = vec![20.0_f32];
+        for idx in 1..count as usize {
+            let change = if (27..=30).contains(&idx) { 5.0 } else { -0.25 };
+            fuel.push(fuel[idx - 1] + change);
+        }
+
+        IbtFile::from_bytes(&test_ibt_with_vars(&[
+            (
+                "SessionTime",
+                "s",
+                TestValues::Double((0..count).map(f64::from).collect()),
+            ),
+            (
+                "Lap",
+                "",
+                TestValues::Int(dist.iter().map(|d| d / 10).collect()),
+            ),
+            (
+                "LapDistPct",
+                "%",
+                TestValues::Float(dist.iter().map(|d| (d % 10) as f32 / 10.0).collect()),
+            ),
+            ("FuelLevel", "l", TestValues::Float(fuel)),
+            (
+                "FuelUsePerHour",
+                "kg/h",
+                TestValues::Float((0..count).map(|i| i as f32).collect()),
+            ),
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn measures_fuel_use() {
+        let report = assert_ok!(test_file().fuel_report());
+
+        let laps: Vec<_> = report
+            .laps
+            .iter()
+            .map(|l| (l.lap, l.used, l.added, l.clean))
+            .collect();
+        assert_eq!(
+            laps,
+            [
+                (0, 1.0, 0.0, false),
+                (1, 2.5, 0.0, true),
+                (2, 2.5, 0.0, true),
+                (3, 1.5, 20.0, false),
+                (4, 1.5, 0.0, false),
+            ]
+        );
+
+        let use_per_hour: Vec<_> = report.laps.iter().map(|l| l.use_per_hour).collect();
+        assert_eq!(
+            use_per_hour,
+            [Some(2.0), Some(9.5), Some(19.5), Some(29.5), Some(37.5)]
+        );
+
+        assert_eq!(report.refuels.len(), 1);
+        assert_eq!(report.refuels[0].sample, 27);
+        assert_eq!(report.refuels[0].lap, 3);
+        assert_eq!(report.refuels[0].amount, 20.0);
+
+        assert_eq!(
+            report.rolling_average(2),
+            [None, Some(2.5), Some(2.5), Some(2.5), Some(2.5)]
+        );
+        assert_eq!(assert_some!(report.laps_remaining(10.0, 5)), 4.0);
+
+        let stints = report.stints();
+        assert_eq!(stints.len(), 2);
+        assert_eq!(stints[0].laps, 0..3);
+        assert_eq!(stints[0].used, 6.0);
+        assert_eq!(stints[1].laps, 3..5);
+    }
+}
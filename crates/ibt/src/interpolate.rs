@@ -0,0 +1,175 @@
+//! Looking up samples by `SessionTime` rather than by index
+//!
+//! Samples are found with a binary search, so `SessionTime` must not decrease through the file.
+
+use crate::{
+    IbtFile,
+    telemetry::{Sample, TypedReadError, TypedVar, Value, VarHeader},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TimeLookupError {
+    #[error(transparent)]
+    Var(#[from] TypedReadError),
+
+    /// The time lies before the first or after the last sample
+    #[error("session time `{time}` is outside of the file, which runs from `{start}` to `{end}`")]
+    OutOfRange { time: f64, start: f64, end: f64 },
+
+    #[error("file has no samples")]
+    NoSamples,
+}
+
+impl IbtFile {
+    /// Find the index of the last sample at or before a `SessionTime`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file has no `SessionTime` var or the time lies outside of the
+    /// file.
+    pub fn sample_index_at_time(&self, session_time: f64) -> Result<usize, TimeLookupError> {
+        self.locate(session_time).map(|(idx, _)| idx)
+    }
+
+    /// The last sample at or before a `SessionTime`
+    ///
+    /// # Errors
+    ///
+    /// See [`IbtFile::sample_index_at_time`].
+    pub fn sample_at_time(&self, session_time: f64) -> Result<Sample<'_>, TimeLookupError> {
+        Ok(self.sample(self.sample_index_at_time(session_time)?))
+    }
+
+    /// The value of a var at a `SessionTime`, between samples
+    ///
+    /// `Float` and `Double` values, including arrays, are interpolated linearly between the
+    /// samples on either side. Other values are held from the sample before, as enums,
+    /// bitfields, bools and ints like `Gear` or `Lap` have no meaningful value in between.
+    ///
+    /// # Errors
+    ///
+    /// See [`IbtFile::sample_index_at_time`].
+    pub fn interpolate(
+        &self,
+        var: &VarHeader,
+        session_time: f64,
+    ) -> Result<Value, TimeLookupError> {
+        let (idx, frac) = self.locate(session_time)?;
+        let before = self.sample(idx).read_var(var);
+        if frac == 0.0 {
+            return Ok(before);
+        }
+        let after = self.sample(idx + 1).read_var(var);
+        Ok(lerp(before, &after, frac))
+    }
+
+    /// The index of the last sample at or before a time, and how far the time is towards the
+    /// next sample, from 0 to 1
+    fn locate(&self, session_time: f64) -> Result<(usize, f64), TimeLookupError> {
+        let var: TypedVar<f64> = self.vars.typed("SessionTime")?;
+        let time = |idx| self.sample(idx).read(var);
+
+        let count = self.sample_count();
+        if count == 0 {
+            return Err(TimeLookupError::NoSamples);
+        }
+        let (start, end) = (time(0), time(count - 1));
+        if !(start..=end).contains(&session_time) {
+            return Err(TimeLookupError::OutOfRange {
+                time: session_time,
+                start,
+                end,
+            });
+        }
+
+        // first sample after the time
+        let (mut lo, mut hi) = (0, count);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if time(mid) <= session_time {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        let idx = lo - 1;
+
+        let frac = if lo < count {
+            let (t0, t1) = (time(idx), time(lo));
+            if t1 > t0 {
+                (session_time - t0) / (t1 - t0)
+            } else {
+                0.0
+            }
+        } else {
+            0.0
+        };
+        Ok((idx, frac))
+    }
+}
+
+/// Linearly interpolate between two values of the same var, holding values that aren't floats
+pub(crate) fn lerp(before: Value, after: &Value, frac: f64) -> Value {
+    let lerp64 = |a: f64, b: f64| a + (b - a) * frac;
+    let lerp32 = |a: f32, b: f32| lerp64(f64::from(a), f64::from(b)) as f32;
+
+    match (before, after) {
+        (Value::Float(a), Value::Float(b)) => Value::Float(lerp32(a, *b)),
+        (Value::Double(a), Value::Double(b)) => Value::Double(lerp64(a, *b)),
+        (Value::FloatArray(a), Value::FloatArray(b)) => {
+            Value::FloatArray(a.iter().zip(b).map(|(a, b)| lerp32(*a, *b)).collect())
+        }
+        (Value::DoubleArray(a), Value::DoubleArray(b)) => {
+            Value::DoubleArray(a.iter().zip(b).map(|(a, b)| lerp64(*a, *b)).collect())
+        }
+        (before, _) => before,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_matches, assert_ok};
+
+    use crate::{
+        IbtFile, interpolate::TimeLookupError, telemetry::Value, test_utils::test_ibt_with_laps,
+    };
+
+    #[test]
+    fn finds_samples_by_time() {
+        let file = IbtFile::from_bytes(&test_ibt_with_laps(&[
+            (0.0, 1),
+            (1.0, 1),
+            (2.0, 2),
+            (3.0, 2),
+        ]))
+        .unwrap();
+
+        assert_eq!(assert_ok!(file.sample_index_at_time(0.0)), 0);
+        assert_eq!(assert_ok!(file.sample_index_at_time(1.5)), 1);
+        assert_eq!(assert_ok!(file.sample_index_at_time(2.0)), 2);
+        assert_eq!(assert_ok!(file.sample_index_at_time(3.0)), 3);
+        assert_matches!(
+            file.sample_at_time(3.5),
+            Err(TimeLookupError::OutOfRange { end: 3.0, .. })
+        );
+        assert_matches!(
+            file.sample_at_time(-1.0),
+            Err(TimeLookupError::OutOfRange { start: 0.0, .. })
+        );
+    }
+
+    #[test]
+    fn interpolates_between_samples() {
+        let file =
+            IbtFile::from_bytes(&test_ibt_with_laps(&[(0.0, 1), (1.0, 1), (2.0, 2)])).unwrap();
+        let speed = file.vars.var("Speed").unwrap();
+        let lap = file.vars.var("Lap").unwrap();
+
+        assert_matches!(
+            file.interpolate(speed, 1.25),
+            Ok(Value::Float(v)) if (v - 2.5).abs() < 1e-6
+        );
+        assert_matches!(file.interpolate(speed, 2.0), Ok(Value::Float(4.0)));
+        assert_matches!(file.interpolate(lap, 1.75), Ok(Value::Int(1)));
+    }
+}
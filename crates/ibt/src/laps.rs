@@ -0,0 +1,328 @@
+//! Splitting a file into laps
+//!
+//! The `Lap` var doesn't always change on the same sample as `LapDistPct` wraps around at the
+//! start/finish line, so laps are split where `LapDistPct` wraps near a change of `Lap`. The
+//! time the line was crossed is interpolated between the samples on either side of it.
+
+use std::ops::Range;
+
+use crate::{
+    IbtFile,
+    telemetry::{Sample, TypedReadError},
+};
+
+/// How many samples apart a change of `Lap` and the wrap of `LapDistPct` can be
+const MAX_LAP_LAG: usize = 10;
+
+/// A lap of a file
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lap {
+    /// The value of the `Lap` var during the lap
+    pub number: i32,
+    /// Indices of the lap's samples, never empty
+    pub sample_range: Range<usize>,
+    /// `SessionTime` at the start of the lap, in seconds
+    pub start_time: f64,
+    /// `SessionTime` at the end of the lap, in seconds
+    pub end_time: f64,
+    /// Time from crossing the line to crossing it again, in seconds
+    ///
+    /// `None` if the lap didn't start or end at the line, like the partial laps at the start
+    /// and end of a file or a lap cut short by a reset.
+    pub lap_time: Option<f64>,
+    /// The lap started on pit road
+    pub out_lap: bool,
+    /// The lap ended on pit road
+    pub in_lap: bool,
+    /// The car was on pit road at some point during the lap
+    pub pit: bool,
+}
+
+impl Lap {
+    /// Whether the lap runs from crossing the line to crossing it again
+    pub fn is_complete(&self) -> bool {
+        self.lap_time.is_some()
+    }
+
+    /// The lap's samples
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lap is not from the given file.
+    pub fn samples<'file>(&self, file: &'file IbtFile) -> impl Iterator<Item = Sample<'file>> {
+        self.sample_range.clone().map(|idx| file.sample(idx))
+    }
+}
+
+/// Where one lap ends and the next begins
+struct Boundary {
+    idx: usize,
+    /// The value `Lap` changed to
+    number: i32,
+    /// Interpolated `SessionTime` of the line crossing, if the lap ended at the line
+    crossing: Option<f64>,
+}
+
+/// The vars laps are derived from
+struct LapVars {
+    times: Vec<f64>,
+    laps: Vec<i32>,
+    dist_pct: Option<Vec<f32>>,
+    lap_times: Option<Vec<f32>>,
+    on_pit_road: Option<Vec<bool>>,
+}
+
+impl LapVars {
+    /// The interpolated time the line was crossed between samples `idx - 1` and `idx`, if it was
+    fn crossing(&self, idx: usize) -> Option<f64> {
+        let (t0, t1) = (self.times[idx - 1], self.times[idx]);
+        if let Some(pct) = &self.dist_pct {
+            let (p0, p1) = (f64::from(pct[idx - 1]), f64::from(pct[idx]));
+            if p0 - p1 <= 0.5 {
+                return None;
+            }
+            let (before, after) = ((1.0 - p0).max(0.0), p1.max(0.0));
+            let frac = if before + after > 0.0 {
+                before / (before + after)
+            } else {
+                0.0
+            };
+            Some(t0 + frac * (t1 - t0))
+        } else {
+            // without `LapDistPct`, fall back to the lap timer being reset
+            let lap_times = self.lap_times.as_ref()?;
+            let (c0, c1) = (f64::from(lap_times[idx - 1]), f64::from(lap_times[idx]));
+            (c1 < c0).then(|| (t1 - c1).clamp(t0, t1))
+        }
+    }
+
+    /// Find where each lap starts, after the first one
+    fn boundaries(&self) -> Vec<Boundary> {
+        let len = self.laps.len();
+        let mut boundaries: Vec<Boundary> = Vec::new();
+
+        for idx in 1..len {
+            if self.laps[idx] == self.laps[idx - 1] {
+                continue;
+            }
+            let min = boundaries.last().map_or(1, |b| b.idx + 1);
+            // the crossing closest to the change of `Lap`
+            let crossing = (0..=MAX_LAP_LAG)
+                .flat_map(|lag| [idx.checked_sub(lag), Some(idx + lag)])
+                .flatten()
+                .filter(|i| (min..len).contains(i))
+                .find_map(|i| Some((i, self.crossing(i)?)));
+
+            match (crossing, boundaries.last_mut()) {
+                // `Lap` changed again before the crossing already used for the previous change,
+                // so both changes end the same lap
+                (None, Some(last)) if idx < min => last.number = self.laps[idx],
+                _ => boundaries.push(Boundary {
+                    idx: crossing.map_or(idx, |(i, _)| i),
+                    number: self.laps[idx],
+                    crossing: crossing.map(|(_, time)| time),
+                }),
+            }
+        }
+        boundaries
+    }
+
+    fn on_pit_road(&self, idx: usize) -> bool {
+        self.on_pit_road.as_ref().is_some_and(|p| p[idx])
+    }
+}
+
+impl IbtFile {
+    /// Split the file into laps
+    ///
+    /// Laps are derived from the `SessionTime` and `Lap` vars, with `LapDistPct` or
+    /// `LapCurrentLapTime` used to find the line crossings and `OnPitRoad` for the pit flags.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file has no `SessionTime` or `Lap` var, or one of the vars has an
+    /// unexpected type.
+    pub fn laps(&self) -> Result<Vec<Lap>, TypedReadError> {
+        let vars = LapVars {
+            times: self.column("SessionTime")?,
+            laps: self.column("Lap")?,
+            dist_pct: self.optional_column("LapDistPct")?,
+            lap_times: self.optional_column("LapCurrentLapTime")?,
+            on_pit_road: self.optional_column("OnPitRoad")?,
+        };
+        if vars.laps.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let boundaries = vars.boundaries();
+        let starts = std::iter::once(None).chain(boundaries.iter().map(Some));
+        let ends = boundaries.iter().map(Some).chain(std::iter::once(None));
+
+        let last = vars.laps.len() - 1;
+        let laps = starts
+            .zip(ends)
+            .map(|(start, end)| {
+                let start_idx = start.map_or(0, |b| b.idx);
+                let end_idx = end.map_or(last + 1, |b| b.idx);
+                let start_crossing = start.and_then(|b| b.crossing);
+                let end_crossing = end.and_then(|b| b.crossing);
+
+                let start_time = start_crossing.unwrap_or(vars.times[start_idx]);
+                let end_time = end_crossing.unwrap_or(vars.times[end_idx.min(last)]);
+
+                Lap {
+                    number: start.map_or(vars.laps[0], |b| b.number),
+                    sample_range: start_idx..end_idx,
+                    start_time,
+                    end_time,
+                    lap_time: start_crossing
+                        .zip(end_crossing)
+                        .map(|(start, end)| end - start),
+                    out_lap: vars.on_pit_road(start_idx),
+                    in_lap: vars.on_pit_road(end_idx - 1),
+                    pit: (start_idx..end_idx).any(|i| vars.on_pit_road(i)),
+                }
+            })
+            .collect();
+
+        Ok(laps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_none, assert_ok, assert_some};
+
+    use crate::{
+        IbtFile,
+        test_utils::{TestValues, test_ibt_with_vars},
+    };
+
+    /// A car doing laps of 10 s, sampled every second, with `Lap` changing one sample after
+    /// `LapDistPct` wraps
+    fn test_file(on_pit_road: &[bool]) -> IbtFile {
+        let count = on_pit_road.len();
+        let times: Vec<f64> = (0..count).map(|i| i as f64 + 5.5).collect();
+        let pct = times.iter().map(|t| ((t / 10.0) % 1.0) as f32).collect();
+        let laps = times
+            .iter()
+            .enumerate()
+            .map(|(i, _)| (times[i.saturating_sub(1)] / 10.0) as i32 + 1)
+            .collect();
+
+        IbtFile::from_bytes(&test_ibt_with_vars(&[
+            ("SessionTime", "s", TestValues::Double(times)),
+            ("Lap", "", TestValues::Int(laps)),
+            ("LapDistPct", "%", TestValues::Float(pct)),
+            ("OnPitRoad", "", TestValues::Bool(on_pit_road.to_vec())),
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn splits_laps_at_the_line() {
+        let mut pits = vec![false; 25];
+        pits[0] = true;
+        let file = test_file(&pits);
+        let laps = assert_ok!(file.laps());
+
+        assert_eq!(laps.len(), 3);
+        assert_eq!(laps.iter().map(|l| l.number).collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(laps[0].sample_range, 0..5);
+        assert_eq!(laps[1].sample_range, 5..15);
+        assert_eq!(laps[2].sample_range, 15..25);
+
+        assert_none!(laps[0].lap_time);
+        assert!(laps[0].out_lap && laps[0].pit);
+        let lap_time = assert_some!(laps[1].lap_time);
+        assert!((lap_time - 10.0).abs() < 1e-4);
+        assert!((laps[1].start_time - 10.0).abs() < 1e-4);
+        assert!(!laps[1].pit);
+        assert_none!(laps[2].lap_time);
+
+        assert_eq!(laps[1].samples(&file).count(), 10);
+    }
+
+    #[test]
+    fn flags_in_laps() {
+        let mut pits = vec![false; 15];
+        pits[13] = true;
+        pits[14] = true;
+        let laps = assert_ok!(test_file(&pits).laps());
+
+        assert!(laps[1].in_lap && laps[1].pit);
+        assert!(!laps[0].in_lap && !laps[0].pit);
+    }
+
+    #[test]
+    fn merges_lap_changes_before_the_line() {
+        // `Lap` changes twice in the samples before `LapDistPct` wraps
+        let laps: Vec<i32> = (0..30)
+            .map(|i| match i {
+                0..3 => 1,
+                3 => 2,
+                4..15 => 3,
+                15..25 => 4,
+                _ => 5,
+            })
+            .collect();
+        let file = IbtFile::from_bytes(&test_ibt_with_vars(&[
+            (
+                "SessionTime",
+                "s",
+                TestValues::Double((0..30).map(f64::from).collect()),
+            ),
+            ("Lap", "", TestValues::Int(laps)),
+            (
+                "LapDistPct",
+                "%",
+                TestValues::Float((0..30).map(|i| ((i + 5) % 10) as f32 / 10.0).collect()),
+            ),
+        ]))
+        .unwrap();
+        let laps = assert_ok!(file.laps());
+
+        let ranges: Vec<_> = laps
+            .iter()
+            .map(|l| (l.number, l.sample_range.clone()))
+            .collect();
+        assert_eq!(ranges, [(1, 0..5), (3, 5..15), (4, 15..25), (5, 25..30)]);
+    }
+
+    #[test]
+    fn falls_back_to_the_lap_timer() {
+        // no `LapDistPct`, the lap timer resets half a second before each sample where `Lap`
+        // has changed one sample later
+        let file = IbtFile::from_bytes(&test_ibt_with_vars(&[
+            (
+                "SessionTime",
+                "s",
+                TestValues::Double((0..25).map(f64::from).collect()),
+            ),
+            (
+                "Lap",
+                "",
+                TestValues::Int((0..25).map(|i| (i + 4) / 10 + 1).collect()),
+            ),
+            (
+                "LapCurrentLapTime",
+                "s",
+                TestValues::Float(
+                    (0..25)
+                        .map(|i| ((f64::from(i) + 5.5) % 10.0) as f32)
+                        .collect(),
+                ),
+            ),
+        ]))
+        .unwrap();
+        let laps = assert_ok!(file.laps());
+
+        assert_eq!(laps.len(), 3);
+        assert_eq!(laps[1].sample_range, 5..15);
+        assert!((laps[1].start_time - 4.5).abs() < 1e-4);
+        let lap_time = assert_some!(laps[1].lap_time);
+        assert!((lap_time - 10.0).abs() < 1e-4);
+        assert_none!(laps[0].lap_time);
+    }
+}
@@ -3,13 +3,41 @@
 //! [ir]: https://iracing.com
 
 mod aligned;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod columns;
+#[cfg(feature = "csv")]
+pub mod csv;
+pub mod delta;
+pub mod events;
 mod file;
+pub mod fuel;
+pub mod interpolate;
+pub mod laps;
+pub mod merge;
+#[cfg(feature = "parquet")]
+pub mod parquet;
 pub mod raw;
+mod reader;
+pub mod recovery;
+pub mod resample;
+mod runs;
+pub mod sectors;
+pub mod session;
+pub mod slice;
 pub mod telemetry;
+pub mod track_map;
+mod writer;
 
 #[cfg(test)]
 mod test_utils;
 
+#[cfg(feature = "arrow")]
+pub use arrow_array;
+#[cfg(feature = "arrow")]
+pub use arrow_schema;
 pub use file::{IbtFile, IbtFileError};
 pub use raw::RawTelemError;
+pub use reader::IbtReader;
 pub use saphyr;
+pub use writer::{IbtWriter, IbtWriterError};
@@ -0,0 +1,273 @@
+//! Combining several files from the same session
+//!
+//! iRacing starts a new `.ibt` file every time the driver gets back in the car, so a single
+//! session is often spread across several files.
+
+use std::io::{Seek, Write};
+
+use crate::{
+    IbtFile, IbtWriter, IbtWriterError,
+    telemetry::{Sample, Value},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum MergeError {
+    #[error(transparent)]
+    Write(#[from] IbtWriterError),
+
+    /// There were no files to merge
+    #[error("no files to merge")]
+    NoFiles,
+
+    /// A file's vars or sample length differ from the first file's
+    #[error("file `{index}` has different vars than file `0`")]
+    IncompatibleVars { index: usize },
+
+    /// A file's session string could not be parsed
+    #[error("session string of file `{index}` could not be parsed")]
+    InvalidSessionString {
+        index: usize,
+        #[source]
+        source: saphyr::ScanError,
+    },
+
+    /// A file is missing the `WeekendInfo.SessionID` or `WeekendInfo.SubSessionID` keys
+    #[error("file `{index}` has no session ID")]
+    MissingSessionId { index: usize },
+
+    /// A file is from a different session than the first file
+    #[error("file `{index}` is from session `{found:?}`, expected `{expected:?}`")]
+    DifferentSession {
+        index: usize,
+        /// `(SessionID, SubSessionID)` of the first file
+        expected: (i64, i64),
+        /// `(SessionID, SubSessionID)` of this file
+        found: (i64, i64),
+    },
+}
+
+/// A jump in `SessionTime` between two consecutive samples of the merged files
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gap {
+    /// Index in the merged samples of the first sample after the gap
+    pub sample: usize,
+    /// `SessionTime` before the gap
+    pub from: f64,
+    /// `SessionTime` after the gap
+    pub to: f64,
+}
+
+/// Describes how files were merged
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MergeReport {
+    /// Indices of the input files, in the order their samples were merged
+    pub order: Vec<usize>,
+    /// Total number of samples
+    pub record_count: usize,
+    /// Jumps in `SessionTime` longer than one and a half ticks
+    pub gaps: Vec<Gap>,
+}
+
+/// Several files from the same session, ordered by `SessionTime`
+///
+/// Obtained from [`merge`].
+#[derive(Clone, Debug)]
+pub struct Merged<'file> {
+    files: Vec<&'file IbtFile>,
+    pub report: MergeReport,
+}
+
+/// Check that files come from the same session and order them by `SessionTime`
+///
+/// Files are compatible when they have identical vars and come from the same `SessionID` and
+/// `SubSessionID`. They are ordered by the `SessionTime` of their first sample, falling back to
+/// the disk sub-header's start time.
+///
+/// # Errors
+///
+/// Returns an error if the files are incompatible or no files are given.
+pub fn merge<'file, I>(files: I) -> Result<Merged<'file>, MergeError>
+where
+    I: IntoIterator<Item = &'file IbtFile>,
+{
+    let mut files: Vec<_> = files.into_iter().enumerate().collect();
+    let Some((_, first)) = files.first() else {
+        return Err(MergeError::NoFiles);
+    };
+
+    let expected = session_id(first, 0)?;
+    for (index, file) in &files[1..] {
+        if file.header.buf_len != first.header.buf_len
+            || !file.vars.all_vars().eq(first.vars.all_vars())
+        {
+            return Err(MergeError::IncompatibleVars { index: *index });
+        }
+        let found = session_id(file, *index)?;
+        if found != expected {
+            return Err(MergeError::DifferentSession {
+                index: *index,
+                expected,
+                found,
+            });
+        }
+    }
+
+    files.sort_by(|(_, a), (_, b)| start_time(a).total_cmp(&start_time(b)));
+    let (order, files): (Vec<_>, Vec<_>) = files.into_iter().unzip();
+
+    let mut merged = Merged {
+        files,
+        report: MergeReport {
+            order,
+            ..MergeReport::default()
+        },
+    };
+    merged.report.record_count = merged.files.iter().map(|f| f.sample_count()).sum();
+    merged.report.gaps = merged.find_gaps();
+    Ok(merged)
+}
+
+/// `(SessionID, SubSessionID)` from a file's session string
+fn session_id(file: &IbtFile, index: usize) -> Result<(i64, i64), MergeError> {
+    let session = file
+        .session_data()
+        .map_err(|source| MergeError::InvalidSessionString { index, source })?;
+    let weekend_info = session.as_mapping_get("WeekendInfo");
+    let id = |key| weekend_info?.as_mapping_get(key)?.as_integer();
+
+    id("SessionID")
+        .zip(id("SubSessionID"))
+        .ok_or(MergeError::MissingSessionId { index })
+}
+
+fn start_time(file: &IbtFile) -> f64 {
+    let first = file
+        .vars
+        .var("SessionTime")
+        .zip(file.get(0))
+        .map(|(var, sample)| sample.read_var(var));
+    match first {
+        Some(Value::Double(time)) => time,
+        _ => file.disk_sub_header.start_time.as_secs_f64(),
+    }
+}
+
+impl<'file> Merged<'file> {
+    /// Iterate over the samples of all files, in order
+    pub fn samples(&self) -> impl Iterator<Item = Sample<'file>> + '_ {
+        self.files.iter().flat_map(|f| f.samples())
+    }
+
+    /// Write all samples to a new IBT file
+    ///
+    /// The new file uses the session string of the last file, which has the most up to date
+    /// session results, and the date of the first. Its disk sub-header is recomputed from the
+    /// samples, see [`IbtWriter::disk_sub_header`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an IO error occurs.
+    pub fn write<W: Write + Seek>(&self, out: W) -> Result<W, MergeError> {
+        let first = self.files[0];
+        let last = self.files[self.files.len() - 1];
+
+        let mut writer = IbtWriter::new(out, &first.header, &first.vars, last.session_bytes())?;
+        for sample in self.samples() {
+            writer.write_sample(sample.as_bytes())?;
+        }
+        Ok(writer.finish(first.disk_sub_header.date)?)
+    }
+
+    fn find_gaps(&self) -> Vec<Gap> {
+        let first = self.files[0];
+        let Some(var) = first.vars.var("SessionTime") else {
+            return Vec::new();
+        };
+        let max_step = 1.5 / f64::from(first.header.tick_rate.max(1));
+
+        let times: Vec<_> = self
+            .samples()
+            .map(|s| match s.read_var(var) {
+                Value::Double(time) => time,
+                _ => f64::NAN,
+            })
+            .collect();
+        times
+            .windows(2)
+            .enumerate()
+            .filter(|(_, pair)| pair[1] - pair[0] > max_step)
+            .map(|(idx, pair)| Gap {
+                sample: idx + 1,
+                from: pair[0],
+                to: pair[1],
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use claims::{assert_matches, assert_ok};
+
+    use crate::{
+        IbtFile, IbtWriter,
+        merge::{Gap, MergeError, merge},
+        test_utils::test_ibt,
+    };
+
+    fn ticks(start: u32, count: u32) -> Vec<f64> {
+        (start..start + count)
+            .map(|t| f64::from(t) / 60.0)
+            .collect()
+    }
+
+    #[test]
+    fn merges_files_in_order() {
+        let late = assert_ok!(IbtFile::from_bytes(&test_ibt(&ticks(120, 3))));
+        let early = assert_ok!(IbtFile::from_bytes(&test_ibt(&ticks(0, 4))));
+
+        let merged = assert_ok!(merge([&late, &early]));
+        assert_eq!(merged.report.order, [1, 0]);
+        assert_eq!(merged.report.record_count, 7);
+        assert_eq!(
+            merged.report.gaps,
+            [Gap {
+                sample: 4,
+                from: 3.0 / 60.0,
+                to: 2.0
+            }]
+        );
+
+        let out = assert_ok!(merged.write(Cursor::new(Vec::new())));
+        let file = assert_ok!(IbtFile::from_bytes(&out.into_inner()));
+        assert_eq!(file.sample_count(), 7);
+        assert_eq!(file.sample(0).as_bytes(), early.sample(0).as_bytes());
+        assert_eq!(file.sample(6).as_bytes(), late.sample(2).as_bytes());
+    }
+
+    #[test]
+    fn rejects_different_session() {
+        let first = assert_ok!(IbtFile::from_bytes(&test_ibt(&ticks(0, 2))));
+
+        let mut writer = assert_ok!(IbtWriter::new(
+            Cursor::new(Vec::new()),
+            &first.header,
+            &first.vars,
+            b"---\nWeekendInfo:\n SessionID: 1\n SubSessionID: 3\n...\n",
+        ));
+        assert_ok!(writer.write_sample(first.sample(0).as_bytes()));
+        let out = assert_ok!(writer.finish(first.disk_sub_header.date));
+        let other = assert_ok!(IbtFile::from_bytes(&out.into_inner()));
+
+        assert_matches!(
+            merge([&first, &other]),
+            Err(MergeError::DifferentSession {
+                index: 1,
+                expected: (1, 2),
+                found: (1, 3)
+            })
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! Export of telemetry to [Apache Parquet][parquet] files
+//!
+//! Columns use the same types and field metadata as the [`arrow`][crate::arrow] conversion. The
+//! disk sub-header, tick rate and session string are stored as key-value file metadata:
+//!
+//! | Key                | Value                                             |
+//! |--------------------|---------------------------------------------------|
+//! | `ibt.date`         | [`DiskSubHeader::date`], as RFC 3339              |
+//! | `ibt.start_time`   | [`DiskSubHeader::start_time`], in seconds         |
+//! | `ibt.end_time`     | [`DiskSubHeader::end_time`], in seconds           |
+//! | `ibt.lap_count`    | [`DiskSubHeader::lap_count`]                      |
+//! | `ibt.record_count` | [`DiskSubHeader::record_count`]                   |
+//! | `ibt.tick_rate`    | [`Header::tick_rate`][crate::telemetry::Header]   |
+//! | `ibt.session_info` | [`IbtFile::raw_session_data`]                     |
+//!
+//! [parquet]: https://parquet.apache.org
+
+use std::io::Write;
+
+use parquet::{
+    arrow::ArrowWriter,
+    errors::ParquetError,
+    file::{metadata::KeyValue, properties::WriterProperties},
+};
+
+pub use parquet::basic::{Compression, ZstdLevel};
+
+use crate::{IbtFile, arrow::ToArrowError, telemetry::DiskSubHeader};
+
+/// Ten minutes of samples at 60 Hz
+pub const DEFAULT_ROW_GROUP_SIZE: usize = 36_000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ParquetExportError {
+    #[error(transparent)]
+    Arrow(#[from] ToArrowError),
+
+    #[error(transparent)]
+    Parquet(#[from] ParquetError),
+}
+
+/// Options for [`IbtFile::write_parquet`]
+#[derive(Clone, Debug)]
+pub struct ParquetOptions {
+    /// Names of the vars to export, or `None` for all vars
+    pub vars: Option<Vec<String>>,
+    /// Maximum number of samples in each row group
+    pub row_group_size: usize,
+    pub compression: Compression,
+}
+
+impl Default for ParquetOptions {
+    fn default() -> Self {
+        Self {
+            vars: None,
+            row_group_size: DEFAULT_ROW_GROUP_SIZE,
+            compression: Compression::ZSTD(ZstdLevel::default()),
+        }
+    }
+}
+
+impl IbtFile {
+    /// Write the file's samples and metadata to a Parquet file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a selected var doesn't exist or the Parquet file can't be written.
+    pub fn write_parquet<W: Write + Send>(
+        &self,
+        out: W,
+        options: &ParquetOptions,
+    ) -> Result<W, ParquetExportError> {
+        let batch = match &options.vars {
+            Some(vars) => self.record_batch(vars)?,
+            None => self.to_record_batch()?,
+        };
+
+        let properties = WriterProperties::builder()
+            .set_max_row_group_size(options.row_group_size.max(1))
+            .set_compression(options.compression)
+            .set_key_value_metadata(Some(self.parquet_metadata()))
+            .build();
+        let mut writer = ArrowWriter::try_new(out, batch.schema(), Some(properties))?;
+        writer.write(&batch)?;
+        Ok(writer.into_inner()?)
+    }
+
+    fn parquet_metadata(&self) -> Vec<KeyValue> {
+        let DiskSubHeader {
+            date,
+            start_time,
+            end_time,
+            lap_count,
+            record_count,
+        } = &self.disk_sub_header;
+
+        [
+            ("ibt.date", date.to_rfc3339()),
+            ("ibt.start_time", start_time.as_secs_f64().to_string()),
+            ("ibt.end_time", end_time.as_secs_f64().to_string()),
+            ("ibt.lap_count", lap_count.to_string()),
+            ("ibt.record_count", record_count.to_string()),
+            ("ibt.tick_rate", self.header.tick_rate.to_string()),
+            ("ibt.session_info", self.raw_session_data()),
+        ]
+        .into_iter()
+        .map(|(key, value)| KeyValue::new(key.to_string(), value))
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use claims::{assert_ok, assert_some};
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    use crate::{
+        IbtFile,
+        parquet::{DEFAULT_ROW_GROUP_SIZE, ParquetOptions},
+        test_utils::test_ibt,
+    };
+
+    #[test]
+    fn writes_samples_and_metadata() {
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt(&[1.0, 1.5, 2.0])));
+        let options = ParquetOptions {
+            vars: Some(vec!["SessionTime".to_string(), "Speed".to_string()]),
+            row_group_size: 2,
+            ..ParquetOptions::default()
+        };
+        assert_eq!(
+            ParquetOptions::default().row_group_size,
+            DEFAULT_ROW_GROUP_SIZE
+        );
+
+        let out = assert_ok!(file.write_parquet(Vec::new(), &options));
+        let reader = assert_ok!(SerializedFileReader::new(Bytes::from(out)));
+        let metadata = reader.metadata();
+        assert_eq!(metadata.num_row_groups(), 2);
+
+        let file_metadata = metadata.file_metadata();
+        assert_eq!(file_metadata.num_rows(), 3);
+        assert_eq!(file_metadata.schema_descr().num_columns(), 2);
+        assert_eq!(file_metadata.schema_descr().column(1).name(), "Speed");
+
+        let key_values = assert_some!(file_metadata.key_value_metadata());
+        let value = |key: &str| {
+            key_values
+                .iter()
+                .find(|kv| kv.key == key)
+                .and_then(|kv| kv.value.clone())
+        };
+        assert_eq!(value("ibt.record_count").as_deref(), Some("3"));
+        assert_eq!(value("ibt.session_info"), Some(file.raw_session_data()));
+    }
+}
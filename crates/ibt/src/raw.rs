@@ -88,7 +88,7 @@ pub struct VarHeader {
     pub offset: c_int,
     pub count: c_int,
     pub count_as_time: c_char,
-    _pad: [c_char; 3],
+    pub(crate) _pad: [c_char; 3],
     pub name: [c_char; IRSDK_MAX_STRING],
     pub desc: [c_char; IRSDK_MAX_DESC],
     pub unit: [c_char; IRSDK_MAX_STRING],
@@ -118,15 +118,54 @@ impl Header {
         }
         Ok(header)
     }
+
+    /// Encode the header as it is laid out in an IBT file
+    pub fn to_bytes(&self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0; HEADER_SIZE];
+        let fields = [
+            self.ver,
+            self.status,
+            self.tick_rate,
+            self.session_info_update,
+            self.session_info_len,
+            self.session_info_offset,
+            self.num_vars,
+            self.var_header_offset,
+            self.num_buf,
+            self.buf_len,
+        ];
+        for (chunk, field) in bytes.chunks_exact_mut(4).zip(fields) {
+            chunk.copy_from_slice(&field.to_le_bytes());
+        }
+
+        let var_bufs_offset = std::mem::offset_of!(Header, var_bufs);
+        let var_bufs = bytes[var_bufs_offset..].chunks_exact_mut(size_of::<VarBuf>());
+        for (chunk, var_buf) in var_bufs.zip(&self.var_bufs) {
+            chunk[0..4].copy_from_slice(&var_buf.tick_count.to_le_bytes());
+            chunk[4..8].copy_from_slice(&var_buf.buf_offset.to_le_bytes());
+        }
+
+        bytes
+    }
 }
 
 impl DiskSubHeader {
     pub fn from_raw_bytes(bytes: &[u8]) -> Self {
         *bytemuck::from_bytes(bytes)
     }
+
+    /// Encode the disk sub-header as it is laid out in an IBT file
+    pub fn to_bytes(&self) -> [u8; SUB_HEADER_SIZE] {
+        let mut bytes = [0; SUB_HEADER_SIZE];
+        bytes[0..8].copy_from_slice(&self.start_date.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.start_time.to_le_bytes());
+        bytes[16..24].copy_from_slice(&self.end_time.to_le_bytes());
+        bytes[24..28].copy_from_slice(&self.lap_count.to_le_bytes());
+        bytes[28..32].copy_from_slice(&self.record_count.to_le_bytes());
+        bytes
+    }
 }
 
-#[cfg(test)]
 impl VarBuf {
     pub(crate) fn new(tick_count: c_int, buf_offset: c_int) -> Self {
         Self {
@@ -138,9 +177,30 @@ impl VarBuf {
 }
 
 impl VarHeader {
+    /// # Panics
+    ///
+    /// Panics if `bytes` isn't aligned to [`ALIGNMENT`] or its length isn't a multiple of
+    /// [`VAR_HEADER_SIZE`].
     pub fn slice_from_fraw_bytes(bytes: &[u8]) -> &[Self] {
         bytemuck::cast_slice(bytes)
     }
+
+    /// Encode the var header as it is laid out in an IBT file
+    pub fn to_bytes(&self) -> [u8; VAR_HEADER_SIZE] {
+        let mut bytes = [0; VAR_HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&self.ty.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.offset.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.count.to_le_bytes());
+        bytes[12] = self.count_as_time as u8;
+
+        let strings = [&self.name[..], &self.desc[..], &self.unit[..]];
+        let chars = strings.into_iter().flatten().map(|c| *c as u8);
+        for (byte, c) in bytes[16..].iter_mut().zip(chars) {
+            *byte = c;
+        }
+
+        bytes
+    }
 }
 
 #[cfg(test)]
@@ -259,6 +319,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn encodes_raw_headers() {
+        // sampled from an IBT file
+        let raw = include_bytes_aligned!("../test-data/raw_header");
+        let header = Header::from_raw_bytes(&raw).unwrap();
+        assert_eq!(header.to_bytes()[..], raw[..]);
+
+        let raw = include_bytes_aligned!("../test-data/raw_sub_header");
+        let disk_sub_header = DiskSubHeader::from_raw_bytes(&raw);
+        assert_eq!(disk_sub_header.to_bytes()[..], raw[..]);
+
+        let raw = include_bytes_aligned!("../test-data/raw_var_header");
+        let var_header = VarHeader::slice_from_fraw_bytes(&raw)[0];
+        assert_eq!(var_header.to_bytes()[..], raw[..]);
+    }
+
     #[test]
     fn decodes_var_header() {
         // sampled from an IBT file
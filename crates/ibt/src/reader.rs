@@ -0,0 +1,218 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use aligned_vec::{AVec, ConstAlign};
+
+use crate::{
+    IbtFileError,
+    file::{FileHeaders, parse_var_set},
+    raw,
+    telemetry::{DiskSubHeader, Header, Sample, VarBufInfo, VarSet},
+};
+
+/// Reads a `.ibt` file incrementally from any seekable source
+///
+/// Only the headers are read up front. Samples are then read one at a time, so memory use is
+/// bounded by the size of a single sample regardless of the size of the file.
+///
+/// # Example
+/// ```ignore
+/// # use ibt::IbtReader;
+///
+/// let file = std::fs::File::open("example-telemetry-file.ibt").unwrap();
+/// let mut reader = IbtReader::new(std::io::BufReader::new(file)).unwrap();
+/// let header = reader.vars.var("RPM").unwrap().clone();
+///
+/// while let Some(sample) = reader.next_sample().unwrap() {
+///     let rpm = sample.read_var(&header);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct IbtReader<R> {
+    inner: R,
+
+    pub header: Header,
+    pub disk_sub_header: DiskSubHeader,
+
+    /// Lists what variables are available
+    pub vars: VarSet,
+
+    /// IBT files only have on variable buffer containing all samples
+    pub var_buf_info: VarBufInfo,
+
+    /// Index of the sample returned by the next call to `next_sample`
+    next_idx: usize,
+}
+
+impl<R: Read + Seek> IbtReader<R> {
+    /// Read the headers and var headers from the start of `inner`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is invalid or an IO error occurs.
+    pub fn new(mut inner: R) -> Result<Self, IbtFileError> {
+        let file_len = inner.seek(SeekFrom::End(0))? as usize;
+        if file_len < FileHeaders::SIZE {
+            return Err(IbtFileError::TruncatedHeader { len: file_len });
+        }
+
+        inner.seek(SeekFrom::Start(0))?;
+        let headers = FileHeaders::parse(&read_aligned(&mut inner, FileHeaders::SIZE)?)?;
+
+        let vh_range = headers.var_headers_range(file_len)?;
+        inner.seek(SeekFrom::Start(vh_range.start as u64))?;
+        let vh_bytes = read_aligned(&mut inner, vh_range.len())?;
+        let vars = parse_var_set(&vh_bytes, headers.header.buf_len)?;
+        headers.session_range(file_len)?;
+        headers.samples_range(headers.disk_sub_header.record_count, file_len)?;
+
+        let mut reader = Self {
+            inner,
+            header: headers.header,
+            disk_sub_header: headers.disk_sub_header,
+            vars,
+            var_buf_info: headers.var_buf_info,
+            next_idx: 0,
+        };
+        reader.seek_sample(0)?;
+        Ok(reader)
+    }
+
+    /// Number of samples in the file
+    pub fn sample_count(&self) -> usize {
+        self.disk_sub_header.record_count
+    }
+
+    /// Read the session string as a plain String
+    ///
+    /// The position of the next sample is preserved.
+    pub fn raw_session_data(&mut self) -> Result<String, IbtFileError> {
+        let mut session_string = vec![0; self.header.session_info_len];
+        self.inner
+            .seek(SeekFrom::Start(self.header.session_info_offset as u64))?;
+        self.inner.read_exact(&mut session_string)?;
+        self.seek_sample(self.next_idx)?;
+
+        Ok(String::from_utf8_lossy(&session_string).into_owned())
+    }
+
+    /// Move the reader so that the next sample read is the `idx`th sample
+    ///
+    /// Seeking to `sample_count()` is allowed and positions the reader at the end of the samples.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IbtFileError::SampleOutOfRange`] if `idx` is past the end of the samples.
+    pub fn seek_sample(&mut self, idx: usize) -> Result<(), IbtFileError> {
+        if idx > self.sample_count() {
+            return Err(IbtFileError::SampleOutOfRange {
+                idx,
+                count: self.sample_count(),
+            });
+        }
+        let offset = self.var_buf_info.buf_offset + self.header.buf_len * idx;
+        self.inner.seek(SeekFrom::Start(offset as u64))?;
+        self.next_idx = idx;
+        Ok(())
+    }
+
+    /// Read the next sample into a newly allocated buffer
+    ///
+    /// Returns `None` once all samples have been read.
+    pub fn next_sample(&mut self) -> Result<Option<Sample<'static>>, IbtFileError> {
+        let mut buf = vec![0; self.header.buf_len];
+        let sample = self.next_sample_into_buf(&mut buf)?;
+        Ok(sample.map(Sample::into_owned))
+    }
+
+    /// Read the next sample into the given buffer, which must be exactly
+    /// [`Header::buf_len`] bytes long
+    ///
+    /// Returns `None` once all samples have been read.
+    pub fn next_sample_into_buf<'buf>(
+        &mut self,
+        buf: &'buf mut [u8],
+    ) -> Result<Option<Sample<'buf>>, IbtFileError> {
+        if self.next_idx >= self.sample_count() {
+            return Ok(None);
+        }
+
+        self.inner.read_exact(buf)?;
+        self.next_idx += 1;
+        Ok(Some(Sample::new(buf)))
+    }
+
+    /// Consume the reader, returning the underlying source
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read + Seek> Iterator for IbtReader<R> {
+    type Item = Result<Sample<'static>, IbtFileError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_sample().transpose()
+    }
+}
+
+/// Read exactly `len` bytes into a buffer aligned for decoding raw headers
+fn read_aligned<R: Read>(
+    inner: &mut R,
+    len: usize,
+) -> Result<AVec<u8, ConstAlign<{ raw::ALIGNMENT }>>, IbtFileError> {
+    let mut buf = AVec::from_iter(raw::ALIGNMENT, std::iter::repeat_n(0, len));
+    inner.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use claims::{assert_matches, assert_none, assert_ok, assert_some};
+
+    use crate::{IbtFile, IbtFileError, IbtReader, test_utils::test_ibt};
+
+    #[test]
+    fn matches_in_memory_file() {
+        let bytes = test_ibt(&[1.0, 1.5, 2.0, 2.5]);
+        let file = assert_ok!(IbtFile::from_bytes(&bytes));
+        let reader = assert_ok!(IbtReader::new(Cursor::new(&bytes)));
+
+        assert_eq!(reader.header, file.header);
+        assert_eq!(reader.disk_sub_header, file.disk_sub_header);
+        assert_eq!(reader.var_buf_info, file.var_buf_info);
+
+        let var = file.vars.var("Speed").unwrap().clone();
+        let read: Vec<_> = reader
+            .map(|s| format!("{:?}", assert_ok!(s).read_var(&var)))
+            .collect();
+        let expected: Vec<_> = file
+            .samples()
+            .map(|s| format!("{:?}", s.read_var(&var)))
+            .collect();
+        assert_eq!(read, expected);
+    }
+
+    #[test]
+    fn seeks_to_sample() {
+        let bytes = test_ibt(&[1.0, 1.5, 2.0, 2.5]);
+        let mut reader = assert_ok!(IbtReader::new(Cursor::new(&bytes)));
+        let var = reader.vars.var("SessionTime").unwrap().clone();
+
+        assert_ok!(reader.seek_sample(2));
+        let sample = assert_some!(assert_ok!(reader.next_sample()));
+        assert_eq!(format!("{:?}", sample.read_var(&var)), "Double(2.0)");
+
+        // reading the session string doesn't move the reader
+        assert!(assert_ok!(reader.raw_session_data()).contains("TrackName"));
+        let sample = assert_some!(assert_ok!(reader.next_sample()));
+        assert_eq!(format!("{:?}", sample.read_var(&var)), "Double(2.5)");
+
+        assert_none!(assert_ok!(reader.next_sample()));
+        assert_matches!(
+            reader.seek_sample(5),
+            Err(IbtFileError::SampleOutOfRange { idx: 5, count: 4 })
+        );
+    }
+}
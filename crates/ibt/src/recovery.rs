@@ -0,0 +1,338 @@
+//! Opening `.ibt` files that were not finalized
+//!
+//! iRacing only fills in the disk sub-header once it finishes writing a file. If it crashes or the
+//! PC loses power, the file is left with a `record_count` and `lap_count` of zero (or stale
+//! values) even though sample data is on disk.
+
+use std::{ops::Range, path::Path, time::Duration};
+
+use crate::{
+    IbtFile, IbtFileError,
+    file::{FileHeaders, Storage, parse_var_set},
+    telemetry::Value,
+};
+
+/// Marks the start of the session string's YAML document
+const DOC_START: &[u8] = b"---\n";
+/// Marks the end of the session string's YAML document
+const DOC_END: &[u8] = b"\n...\n";
+
+/// Something that had to be repaired to open a file
+#[derive(Clone, Debug, PartialEq)]
+pub enum Repair {
+    /// The session string was not where the header said it was
+    SessionString {
+        stored: Range<usize>,
+        /// Where the session string was found, empty if it could not be found
+        found: Range<usize>,
+    },
+
+    /// The samples did not start where the header said they did
+    BufOffset { stored: usize, inferred: usize },
+
+    /// The number of samples in the disk sub-header didn't match the data on disk
+    RecordCount { stored: usize, inferred: usize },
+
+    /// The file ended partway through a sample, which was discarded
+    PartialSample {
+        /// Number of bytes discarded from the end of the file
+        len: usize,
+    },
+
+    /// The number of laps in the disk sub-header didn't match the `Lap` var
+    LapCount { stored: u32, inferred: u32 },
+
+    /// The end time in the disk sub-header didn't match the `SessionTime` var
+    EndTime {
+        stored: Duration,
+        inferred: Duration,
+    },
+}
+
+/// Describes what had to be repaired while recovering a file
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecoveryReport {
+    pub repairs: Vec<Repair>,
+}
+
+impl RecoveryReport {
+    /// Whether the file was intact and nothing had to be repaired
+    pub fn is_clean(&self) -> bool {
+        self.repairs.is_empty()
+    }
+}
+
+impl IbtFile {
+    /// Open a possibly incomplete IBT file at the given path, repairing what can be repaired
+    ///
+    /// The whole file is read into memory. See [`IbtFile::recover_bytes`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be recovered or an IO error occurs.
+    pub fn recover_file<P: AsRef<Path>>(path: P) -> Result<(Self, RecoveryReport), IbtFileError> {
+        Self::recover_bytes(&std::fs::read(&path)?)
+    }
+
+    /// Parse a possibly incomplete IBT file, repairing what can be repaired
+    ///
+    /// The number of samples is inferred from the length of the file, discarding any partially
+    /// written sample at the end. If the session string is not where the header says it is, it is
+    /// searched for between the var headers and the samples. When the sample count changes, the
+    /// disk sub-header's `lap_count` and `end_time` are recomputed from the `Lap` and `SessionTime`
+    /// vars.
+    ///
+    /// The header, var headers and disk sub-header must still be decodable.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be recovered.
+    pub fn recover_bytes(bytes: &[u8]) -> Result<(Self, RecoveryReport), IbtFileError> {
+        Self::recover_storage(Storage::copy_from(bytes))
+    }
+
+    /// Open a possibly incomplete IBT file at the given path by memory-mapping it
+    ///
+    /// See [`IbtFile::recover_bytes`] and [`IbtFile::open_mmap`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be recovered or an IO error occurs.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped. Doing so is undefined
+    /// behavior.
+    pub unsafe fn recover_mmap<P: AsRef<Path>>(
+        path: P,
+    ) -> Result<(Self, RecoveryReport), IbtFileError> {
+        // SAFETY: the caller guarantees the file is not modified while mapped
+        Self::recover_storage(unsafe { Storage::map(path)? })
+    }
+
+    fn recover_storage(data: Storage) -> Result<(Self, RecoveryReport), IbtFileError> {
+        let mut report = RecoveryReport::default();
+        let mut headers = FileHeaders::parse(&data)?;
+        let vh_range = headers.var_headers_range(data.len())?;
+        let vars = parse_var_set(&data[vh_range.clone()], headers.header.buf_len)?;
+
+        let stored_session = headers.header.session_info_offset
+            ..headers.header.session_info_offset + headers.header.session_info_len;
+        let session = match headers.session_range(data.len()) {
+            Ok(range) if data[range.clone()].starts_with(DOC_START) => range,
+            _ => {
+                let found = find_session_string(&data, vh_range.end);
+                report.repairs.push(Repair::SessionString {
+                    stored: stored_session,
+                    found: found.clone(),
+                });
+                found
+            }
+        };
+        headers.header.session_info_offset = session.start;
+        headers.header.session_info_len = session.len();
+
+        let samples_start = session.end.max(vh_range.end);
+        let stored_buf_offset = headers.var_buf_info.buf_offset;
+        if stored_buf_offset < samples_start || stored_buf_offset > data.len() {
+            report.repairs.push(Repair::BufOffset {
+                stored: stored_buf_offset,
+                inferred: samples_start,
+            });
+            headers.var_buf_info.buf_offset = samples_start;
+        }
+
+        let buf_len = headers.header.buf_len;
+        if buf_len == 0 {
+            return Err(IbtFileError::SamplesOutOfBounds {
+                offset: headers.var_buf_info.buf_offset,
+                record_count: headers.disk_sub_header.record_count,
+            });
+        }
+        let samples_len = data.len() - headers.var_buf_info.buf_offset;
+        let record_count = samples_len / buf_len;
+        let stored_record_count = headers.disk_sub_header.record_count;
+        if record_count != stored_record_count {
+            report.repairs.push(Repair::RecordCount {
+                stored: stored_record_count,
+                inferred: record_count,
+            });
+            headers.disk_sub_header.record_count = record_count;
+        }
+        if samples_len % buf_len != 0 {
+            report.repairs.push(Repair::PartialSample {
+                len: samples_len % buf_len,
+            });
+        }
+
+        let mut file = Self {
+            data,
+            header: headers.header,
+            disk_sub_header: headers.disk_sub_header,
+            vars,
+            var_buf_info: headers.var_buf_info,
+        };
+        if record_count != stored_record_count {
+            file.recompute_lap_count(&mut report);
+            file.recompute_end_time(&mut report);
+        }
+
+        Ok((file, report))
+    }
+
+    fn recompute_lap_count(&mut self, report: &mut RecoveryReport) {
+        let Some(var) = self.vars.var("Lap") else {
+            return;
+        };
+        let laps = self.samples().filter_map(|s| match s.read_var(var) {
+            Value::Int(lap) => u32::try_from(lap).ok(),
+            _ => None,
+        });
+        let Some(inferred) = laps.max() else {
+            return;
+        };
+
+        let stored = self.disk_sub_header.lap_count;
+        if inferred != stored {
+            report.repairs.push(Repair::LapCount { stored, inferred });
+            self.disk_sub_header.lap_count = inferred;
+        }
+    }
+
+    fn recompute_end_time(&mut self, report: &mut RecoveryReport) {
+        let Some(var) = self.vars.var("SessionTime") else {
+            return;
+        };
+        let Some(last) = self.sample_count().checked_sub(1) else {
+            return;
+        };
+        let Value::Double(end_time) = self.sample(last).read_var(var) else {
+            return;
+        };
+        let Ok(inferred) = Duration::try_from_secs_f64(end_time) else {
+            return;
+        };
+
+        let stored = self.disk_sub_header.end_time;
+        if inferred != stored {
+            report.repairs.push(Repair::EndTime { stored, inferred });
+            self.disk_sub_header.end_time = inferred;
+        }
+    }
+}
+
+/// Search for the session string's YAML document after the var headers
+///
+/// Returns an empty range at `search_start` if no document is found.
+fn find_session_string(data: &[u8], search_start: usize) -> Range<usize> {
+    let haystack = &data[search_start..];
+    let Some(start) = find(haystack, DOC_START) else {
+        return search_start..search_start;
+    };
+    let end =
+        find(&haystack[start..], DOC_END).map_or(haystack.len(), |end| start + end + DOC_END.len());
+
+    search_start + start..search_start + end
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use claims::assert_ok;
+
+    use crate::{
+        IbtFile,
+        recovery::{RecoveryReport, Repair},
+        test_utils::test_ibt,
+    };
+
+    /// Offset of the `lap_count` and `record_count` fields in the disk sub-header
+    const LAP_COUNT: usize = 136;
+    const RECORD_COUNT: usize = 140;
+
+    fn zero_sub_header_counts(bytes: &mut [u8]) {
+        bytes[LAP_COUNT..RECORD_COUNT + 4].fill(0);
+    }
+
+    #[test]
+    fn intact_file_needs_no_repairs() {
+        let (file, report) = assert_ok!(IbtFile::recover_bytes(&test_ibt(&[1.0, 1.5, 2.0])));
+        assert!(report.is_clean());
+        assert_eq!(file.sample_count(), 3);
+    }
+
+    #[test]
+    fn infers_record_count_from_file_length() {
+        let mut bytes = test_ibt(&[1.0, 1.5, 2.0]);
+        zero_sub_header_counts(&mut bytes);
+        let file = assert_ok!(IbtFile::from_bytes(&bytes));
+        assert_eq!(file.sample_count(), 0);
+
+        let (file, report) = assert_ok!(IbtFile::recover_bytes(&bytes));
+        assert_eq!(file.sample_count(), 3);
+        assert_eq!(file.disk_sub_header.lap_count, 1);
+        assert_eq!(
+            report,
+            RecoveryReport {
+                repairs: vec![
+                    Repair::RecordCount {
+                        stored: 0,
+                        inferred: 3
+                    },
+                    Repair::LapCount {
+                        stored: 0,
+                        inferred: 1
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn discards_partial_sample() {
+        let bytes = test_ibt(&[1.0, 1.5, 2.0]);
+        let bytes = &bytes[..bytes.len() - 5];
+
+        let (file, report) = assert_ok!(IbtFile::recover_bytes(bytes));
+        assert_eq!(file.sample_count(), 2);
+        assert_eq!(
+            report.repairs,
+            [
+                Repair::RecordCount {
+                    stored: 3,
+                    inferred: 2
+                },
+                Repair::PartialSample { len: 11 },
+                Repair::EndTime {
+                    stored: Duration::from_secs(2),
+                    inferred: Duration::from_secs_f64(1.5)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_misplaced_session_string() {
+        let mut bytes = test_ibt(&[1.0]);
+        let file = assert_ok!(IbtFile::from_bytes(&bytes));
+        let session = file.raw_session_data();
+
+        // session_info_len and session_info_offset
+        bytes[16..24].fill(0);
+
+        let (file, report) = assert_ok!(IbtFile::recover_bytes(&bytes));
+        assert_eq!(file.raw_session_data(), session);
+        assert_eq!(
+            report.repairs,
+            [Repair::SessionString {
+                stored: 0..0,
+                found: 576..576 + session.len()
+            }]
+        );
+    }
+}
@@ -0,0 +1,331 @@
+//! Resampling columns onto a common axis
+//!
+//! Files are recorded at different tick rates and no two laps are driven at the same speed, so
+//! comparing them needs values at the same points in time or along the lap. Values of `Float`
+//! and `Double` vars are interpolated linearly between the samples on either side of each
+//! point; all other values are held from the sample before it.
+
+use std::ops::Range;
+
+use crate::{
+    IbtFile,
+    columns::{Column, ColumnData, Columns},
+    telemetry::TypedReadError,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ResampleError {
+    #[error(transparent)]
+    Var(#[from] TypedReadError),
+
+    /// The sample indices lie outside of the file
+    #[error("samples `{start}..{end}` are out of range, there are `{count}` samples")]
+    OutOfRange {
+        start: usize,
+        end: usize,
+        count: usize,
+    },
+
+    /// The step between points is zero, negative or not finite
+    #[error("resampling step `{0}` must be positive")]
+    InvalidStep(f64),
+}
+
+/// The axis to resample onto
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Axis {
+    /// Every `1 / hz` seconds of `SessionTime`, starting at the first sample
+    Time { hz: f64 },
+    /// Every `step` meters of `LapDist`, at multiples of `step`
+    LapDist { step: f64 },
+    /// Every `step` of `LapDistPct`, at multiples of `step`
+    LapDistPct { step: f64 },
+}
+
+impl Axis {
+    fn step(&self) -> f64 {
+        match self {
+            Self::Time { hz } => 1.0 / hz,
+            Self::LapDist { step } | Self::LapDistPct { step } => *step,
+        }
+    }
+}
+
+/// Columns resampled onto an [`Axis`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Resampled {
+    /// The position of each row on the axis, in seconds, meters or fractions of a lap
+    pub axis: Vec<f64>,
+    pub columns: Columns,
+}
+
+/// Where a point lies between two samples
+#[derive(Clone, Copy, Debug)]
+struct Step {
+    idx: usize,
+    /// How far the point is towards the next sample, from 0 to 1
+    frac: f64,
+}
+
+impl Columns {
+    /// Resample the columns at the given points
+    ///
+    /// `positions` holds the position of each sample on the axis and `points` the positions to
+    /// resample at, both in increasing order. Points before the first sample take its values,
+    /// points after the last sample take the last sample's values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there isn't one position per sample.
+    pub fn resample_at(&self, positions: &[f64], points: &[f64]) -> Columns {
+        assert_eq!(
+            positions.len(),
+            self.sample_count,
+            "there must be one position per sample"
+        );
+        self.apply(&plan(positions, points))
+    }
+
+    fn apply(&self, steps: &[Step]) -> Columns {
+        Columns {
+            sample_count: steps.len(),
+            columns: self
+                .columns
+                .iter()
+                .map(|column| Column {
+                    var: column.var.clone(),
+                    data: resample_data(&column.data, column.var.count, steps),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl IbtFile {
+    /// Resample vars of a range of samples onto an axis
+    ///
+    /// `LapDist` and `LapDistPct` start over on each lap, so resample one lap at a time along
+    /// them, like the [`Lap::sample_range`][crate::laps::Lap::sample_range] of a lap. A car that
+    /// stops or rolls back doesn't move along the axis until it passes its furthest point again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a var is missing, the range is out of bounds or the step is not
+    /// positive.
+    pub fn resample<S: AsRef<str>>(
+        &self,
+        names: &[S],
+        samples: Range<usize>,
+        axis: Axis,
+    ) -> Result<Resampled, ResampleError> {
+        self.resample_where(names, samples, axis, |_| true)
+    }
+
+    /// Like [`IbtFile::resample`], but only interpolating between the samples `keep` returns
+    /// `true` for
+    pub(crate) fn resample_where<S: AsRef<str>>(
+        &self,
+        names: &[S],
+        samples: Range<usize>,
+        axis: Axis,
+        keep: impl Fn(usize) -> bool,
+    ) -> Result<Resampled, ResampleError> {
+        let step = axis.step();
+        if !(step.is_finite() && step > 0.0) {
+            return Err(ResampleError::InvalidStep(step));
+        }
+        if samples.end > self.sample_count() || samples.start > samples.end {
+            return Err(ResampleError::OutOfRange {
+                start: samples.start,
+                end: samples.end,
+                count: self.sample_count(),
+            });
+        }
+        let rows: Vec<usize> = samples.filter(|idx| keep(*idx)).collect();
+
+        let mut positions: Vec<f64> = match axis {
+            Axis::Time { .. } => {
+                let times = self.column::<f64>("SessionTime")?;
+                rows.iter().map(|idx| times[*idx]).collect()
+            }
+            Axis::LapDist { .. } => {
+                let dist = self.column::<f32>("LapDist")?;
+                rows.iter().map(|idx| f64::from(dist[*idx])).collect()
+            }
+            Axis::LapDistPct { .. } => {
+                let pct = self.column::<f32>("LapDistPct")?;
+                rows.iter().map(|idx| f64::from(pct[*idx])).collect()
+            }
+        };
+        // the furthest point reached so far
+        for idx in 1..positions.len() {
+            positions[idx] = positions[idx].max(positions[idx - 1]);
+        }
+
+        let points = match (positions.first(), positions.last()) {
+            (Some(first), Some(last)) => {
+                let first = match axis {
+                    Axis::Time { .. } => *first,
+                    _ => (first / step).ceil() * step,
+                };
+                (0..)
+                    .map(|k| first + f64::from(k) * step)
+                    .take_while(|p| p <= last)
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        let selected: Vec<Step> = rows
+            .iter()
+            .map(|idx| Step {
+                idx: *idx,
+                frac: 0.0,
+            })
+            .collect();
+        let columns = self
+            .columns(names)?
+            .apply(&selected)
+            .apply(&plan(&positions, &points));
+
+        Ok(Resampled {
+            axis: points,
+            columns,
+        })
+    }
+}
+
+/// Find where each point lies between the samples at `positions`
+fn plan(positions: &[f64], points: &[f64]) -> Vec<Step> {
+    if positions.is_empty() {
+        return Vec::new();
+    }
+    let mut idx = 0;
+    points
+        .iter()
+        .map(|point| {
+            while idx + 1 < positions.len() && positions[idx + 1] <= *point {
+                idx += 1;
+            }
+            let frac = match positions.get(idx + 1) {
+                Some(next) if *next > positions[idx] => {
+                    ((point - positions[idx]) / (next - positions[idx])).clamp(0.0, 1.0)
+                }
+                _ => 0.0,
+            };
+            Step { idx, frac }
+        })
+        .collect()
+}
+
+fn resample_data(data: &ColumnData, count: usize, steps: &[Step]) -> ColumnData {
+    fn hold<T: Copy>(values: &[T], count: usize, steps: &[Step]) -> Vec<T> {
+        steps
+            .iter()
+            .flat_map(|s| &values[s.idx * count..(s.idx + 1) * count])
+            .copied()
+            .collect()
+    }
+
+    fn lerp(values: &[f64], count: usize, steps: &[Step]) -> Vec<f64> {
+        steps
+            .iter()
+            .flat_map(|s| {
+                (0..count).map(move |i| {
+                    let before = values[s.idx * count + i];
+                    if s.frac > 0.0 {
+                        let after = values[(s.idx + 1) * count + i];
+                        before + (after - before) * s.frac
+                    } else {
+                        before
+                    }
+                })
+            })
+            .collect()
+    }
+
+    match data {
+        ColumnData::Char(v) => ColumnData::Char(hold(v, count, steps)),
+        ColumnData::Bool(v) => ColumnData::Bool(hold(v, count, steps)),
+        ColumnData::Int(v) => ColumnData::Int(hold(v, count, steps)),
+        ColumnData::Bitfield(v) => ColumnData::Bitfield(hold(v, count, steps)),
+        ColumnData::Float(v) => {
+            let wide: Vec<f64> = v.iter().map(|v| f64::from(*v)).collect();
+            ColumnData::Float(
+                lerp(&wide, count, steps)
+                    .into_iter()
+                    .map(|v| v as f32)
+                    .collect(),
+            )
+        }
+        ColumnData::Double(v) => ColumnData::Double(lerp(v, count, steps)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_matches, assert_ok, assert_some};
+
+    use crate::{
+        IbtFile,
+        columns::ColumnData,
+        resample::{Axis, ResampleError},
+        test_utils::{TestValues, test_ibt, test_ibt_with_vars},
+    };
+
+    #[test]
+    fn resamples_to_fixed_rate() {
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt(&[0.0, 1.0, 2.0, 3.0])));
+        let resampled = assert_ok!(file.resample(&["Speed", "Lap"], 1..4, Axis::Time { hz: 2.0 }));
+
+        assert_eq!(resampled.axis, [1.0, 1.5, 2.0, 2.5, 3.0]);
+        assert_eq!(resampled.columns.sample_count, 5);
+        assert_eq!(
+            assert_some!(resampled.columns.get("Speed")).data,
+            ColumnData::Float(vec![2.0, 3.0, 4.0, 5.0, 6.0])
+        );
+        assert_eq!(
+            assert_some!(resampled.columns.get("Lap")).data,
+            ColumnData::Int(vec![1; 5])
+        );
+
+        assert_matches!(
+            file.resample(&["Speed"], 0..4, Axis::Time { hz: 0.0 }),
+            Err(ResampleError::InvalidStep(_))
+        );
+        assert_matches!(
+            file.resample(&["Speed"], 0..5, Axis::Time { hz: 1.0 }),
+            Err(ResampleError::OutOfRange { .. })
+        );
+    }
+
+    #[test]
+    fn resamples_to_distance() {
+        // the car stops at 10 m and rolls back before carrying on
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt_with_vars(&[
+            (
+                "LapDist",
+                "m",
+                TestValues::Float(vec![2.0, 10.0, 10.0, 8.0, 30.0])
+            ),
+            ("Gear", "", TestValues::Int(vec![1, 2, 2, 1, 3])),
+            (
+                "Speed",
+                "m/s",
+                TestValues::Double(vec![10.0, 20.0, 0.0, 0.0, 20.0])
+            ),
+        ])));
+        let resampled =
+            assert_ok!(file.resample(&["Gear", "Speed"], 0..5, Axis::LapDist { step: 5.0 }));
+
+        assert_eq!(resampled.axis, [5.0, 10.0, 15.0, 20.0, 25.0, 30.0]);
+        assert_eq!(
+            assert_some!(resampled.columns.get("Gear")).data,
+            ColumnData::Int(vec![1, 1, 1, 1, 1, 3])
+        );
+        assert_eq!(
+            assert_some!(resampled.columns.get("Speed")).data,
+            ColumnData::Double(vec![13.75, 0.0, 5.0, 10.0, 15.0, 20.0])
+        );
+    }
+}
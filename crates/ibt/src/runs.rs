@@ -0,0 +1,20 @@
+//! Finding runs of consecutive samples
+
+use std::ops::Range;
+
+/// Sample ranges of each run of `true` values
+pub(crate) fn runs(values: &[bool]) -> Vec<Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (idx, value) in values.iter().chain([&false]).enumerate() {
+        match (value, start) {
+            (true, None) => start = Some(idx),
+            (false, Some(s)) => {
+                runs.push(s..idx);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    runs
+}
@@ -0,0 +1,235 @@
+//! Sector times, from the sectors in the session string's `SplitTimeInfo`
+//!
+//! The time a sector starts is interpolated between the samples on either side of its
+//! `LapDistPct`, the same way [`IbtFile::laps`] interpolates crossing the line.
+
+#[cfg(feature = "csv")]
+use std::io;
+use std::ops::Range;
+
+#[cfg(feature = "csv")]
+use crate::csv::CsvExportError;
+use crate::{IbtFile, laps::Lap, session::SessionInfoError, telemetry::TypedReadError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SectorError {
+    #[error(transparent)]
+    Session(#[from] SessionInfoError),
+
+    #[error(transparent)]
+    Var(#[from] TypedReadError),
+
+    /// The session string doesn't list any sectors
+    #[error("session string has no sectors")]
+    NoSectors,
+}
+
+/// Sector times of one lap, in seconds
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LapSectors {
+    /// The lap's [`Lap::number`]
+    pub lap: i32,
+    /// Time spent in each sector, or `None` if a sector start couldn't be found
+    pub times: Vec<Option<f64>>,
+}
+
+impl LapSectors {
+    /// The sum of the sector times, if all of them are known
+    pub fn total(&self) -> Option<f64> {
+        self.times.iter().copied().sum()
+    }
+}
+
+/// Sector times of every complete lap of a file
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SectorTable {
+    /// Lap distance at which each sector starts, from 0 to 1
+    pub starts: Vec<f64>,
+    pub laps: Vec<LapSectors>,
+}
+
+impl SectorTable {
+    /// The best time of each sector across all laps
+    pub fn best(&self) -> Vec<Option<f64>> {
+        (0..self.starts.len())
+            .map(|sector| {
+                self.laps
+                    .iter()
+                    .filter_map(|lap| lap.times[sector])
+                    .min_by(f64::total_cmp)
+            })
+            .collect()
+    }
+
+    /// The sum of the best sector times, if every sector has one
+    pub fn optimal_lap_time(&self) -> Option<f64> {
+        self.best().into_iter().sum()
+    }
+
+    /// Write the table as CSV, with a row per lap and a final `best` row
+    ///
+    /// Columns are `Lap`, `S1` to `Sn` and `LapTime`, with empty cells for unknown times.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    #[cfg(feature = "csv")]
+    pub fn write_csv<W: io::Write>(&self, out: W) -> Result<W, CsvExportError> {
+        let cell = |time: Option<f64>| time.map(|t| t.to_string()).unwrap_or_default();
+
+        let mut writer = csv::Writer::from_writer(out);
+        let mut header = vec!["Lap".to_string()];
+        header.extend((1..=self.starts.len()).map(|sector| format!("S{sector}")));
+        header.push("LapTime".to_string());
+        writer.write_record(&header)?;
+
+        let best = LapSectors {
+            lap: 0,
+            times: self.best(),
+        };
+        let rows = self
+            .laps
+            .iter()
+            .map(|lap| (lap.lap.to_string(), lap))
+            .chain(std::iter::once(("best".to_string(), &best)));
+        for (name, lap) in rows {
+            let mut row = vec![name];
+            row.extend(lap.times.iter().copied().map(cell));
+            row.push(cell(lap.total()));
+            writer.write_record(&row)?;
+        }
+
+        writer
+            .into_inner()
+            .map_err(|err| CsvExportError::Io(err.into_error()))
+    }
+}
+
+impl IbtFile {
+    /// Time each complete lap's sectors, using the sectors of the session string
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session string can't be parsed or has no sectors, or if the file
+    /// lacks a var needed by [`IbtFile::sector_table_with`].
+    pub fn sector_table(&self) -> Result<SectorTable, SectorError> {
+        let starts: Vec<f64> = self
+            .session_info()?
+            .split_time_info
+            .sectors
+            .iter()
+            .filter_map(|s| s.sector_start_pct)
+            .collect();
+        if starts.is_empty() {
+            return Err(SectorError::NoSectors);
+        }
+        Ok(self.sector_table_with(&starts)?)
+    }
+
+    /// Time each complete lap's sectors, with sectors starting at the given lap distances
+    ///
+    /// The first sector always starts at the line, at `0.0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file has no `LapDistPct` var or lacks a var needed by
+    /// [`IbtFile::laps`].
+    pub fn sector_table_with(&self, starts: &[f64]) -> Result<SectorTable, TypedReadError> {
+        let mut starts: Vec<f64> = starts
+            .iter()
+            .copied()
+            .filter(|s| (0.0..1.0).contains(s))
+            .collect();
+        starts.push(0.0);
+        starts.sort_by(f64::total_cmp);
+        starts.dedup();
+
+        let times: Vec<f64> = self.column("SessionTime")?;
+        let pct: Vec<f32> = self.column("LapDistPct")?;
+        let laps = self
+            .laps()?
+            .iter()
+            .filter(|lap| lap.is_complete())
+            .map(|lap| LapSectors {
+                lap: lap.number,
+                times: sector_times(lap, &starts, &times, &pct),
+            })
+            .collect();
+
+        Ok(SectorTable { starts, laps })
+    }
+}
+
+fn sector_times(lap: &Lap, starts: &[f64], times: &[f64], pct: &[f32]) -> Vec<Option<f64>> {
+    let crossings: Vec<Option<f64>> = std::iter::once(Some(lap.start_time))
+        .chain(
+            starts[1..]
+                .iter()
+                .map(|start| crossing(lap.sample_range.clone(), *start, times, pct)),
+        )
+        .chain(std::iter::once(Some(lap.end_time)))
+        .collect();
+
+    crossings
+        .windows(2)
+        .map(|pair| Some(pair[1]? - pair[0]?))
+        .collect()
+}
+
+/// The interpolated `SessionTime` at which `LapDistPct` passes `target` within the samples
+fn crossing(samples: Range<usize>, target: f64, times: &[f64], pct: &[f32]) -> Option<f64> {
+    (samples.start + 1..samples.end).find_map(|idx| {
+        let (p0, p1) = (f64::from(pct[idx - 1]), f64::from(pct[idx]));
+        if !(p0 < target && target <= p1 && p1 - p0 < 0.5) {
+            return None;
+        }
+        let (t0, t1) = (times[idx - 1], times[idx]);
+        Some(t0 + (target - p0) / (p1 - p0) * (t1 - t0))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_ok, assert_some};
+
+    use crate::{IbtFile, test_utils::test_ibt_with_slow_lap};
+
+    fn test_file() -> IbtFile {
+        IbtFile::from_bytes(&test_ibt_with_slow_lap()).unwrap()
+    }
+
+    fn assert_times(times: &[Option<f64>], expected: &[f64]) {
+        assert_eq!(times.len(), expected.len());
+        for (time, expected) in times.iter().zip(expected) {
+            let time = assert_some!(time);
+            assert!((time - expected).abs() < 1e-4, "{time} != {expected}");
+        }
+    }
+
+    #[test]
+    fn times_sectors_from_session() {
+        let table = assert_ok!(test_file().sector_table());
+
+        assert_eq!(table.starts, [0.0, 0.5]);
+        assert_eq!(table.laps.iter().map(|l| l.lap).collect::<Vec<_>>(), [1, 2]);
+        assert_times(&table.laps[0].times, &[5.0, 5.0]);
+        assert_times(&table.laps[1].times, &[5.0, 9.5]);
+        assert_times(&table.best(), &[5.0, 5.0]);
+        assert!((assert_some!(table.optimal_lap_time()) - 10.0).abs() < 1e-4);
+    }
+
+    #[test]
+    #[cfg(feature = "csv")]
+    fn writes_csv() {
+        let table = assert_ok!(test_file().sector_table_with(&[0.5]));
+        let csv = String::from_utf8(assert_ok!(table.write_csv(Vec::new()))).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "Lap,S1,S2,LapTime");
+        assert!(lines[2].starts_with("2,"));
+        assert!(lines[3].starts_with("best,"));
+    }
+}
@@ -0,0 +1,574 @@
+//! A typed model of the session string
+//!
+//! The session string is a YAML document describing the track, the sessions of the event, the
+//! drivers, and more. [`SessionInfo`] reads the parts of it that are common to all cars and
+//! series. Keys it doesn't know about are ignored and values it can't read are left as `None`,
+//! so a change to the format degrades gracefully instead of failing outright. The raw YAML is
+//! still available from [`IbtFile::session_data`].
+//!
+//! The session string is not always valid YAML, so it is passed through [`sanitize`] first.
+
+mod sanitize;
+mod units;
+mod yaml;
+
+use std::time::Duration;
+
+use indexmap::IndexMap;
+use saphyr::{LoadableYamlNode, ScanError, YamlOwned};
+
+use crate::IbtFile;
+
+pub use sanitize::{Fix, SanitizeReport, sanitize};
+pub use units::{
+    Angle, Distance, Limit, Mass, Pressure, Quantity, Ratio, Speed, Temperature, Volume,
+};
+pub use yaml::FromYaml;
+use yaml::session_struct;
+
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum SessionInfoError {
+    /// The session string is not valid YAML
+    #[error("session string is not valid YAML")]
+    Yaml(#[from] ScanError),
+
+    /// The session string has no YAML document
+    #[error("session string is empty")]
+    Empty,
+}
+
+session_struct! {
+    /// The parsed session string
+    pub struct SessionInfo {
+        "WeekendInfo" => weekend_info: WeekendInfo,
+        "SessionInfo" => session_info: Sessions,
+        "CameraInfo" => camera_info: CameraInfo,
+        "RadioInfo" => radio_info: RadioInfo,
+        "DriverInfo" => driver_info: DriverInfo,
+        "SplitTimeInfo" => split_time_info: SplitTimeInfo,
+        "CarSetup" => car_setup: Option<CarSetup>,
+    }
+}
+
+impl SessionInfo {
+    /// Parse a session string
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the string is not valid YAML, even after sanitizing it. Missing or
+    /// unreadable keys are not errors.
+    pub fn parse(session_string: &str) -> Result<Self, SessionInfoError> {
+        Self::parse_with_report(session_string).map(|(info, _)| info)
+    }
+
+    /// Parse a session string, also returning the values that had to be quoted to parse it
+    ///
+    /// # Errors
+    ///
+    /// See [`SessionInfo::parse`].
+    pub fn parse_with_report(
+        session_string: &str,
+    ) -> Result<(Self, SanitizeReport), SessionInfoError> {
+        let (sanitized, report) = sanitize(session_string);
+        let docs = YamlOwned::load_from_str(&sanitized)?;
+        let doc = docs.first().ok_or(SessionInfoError::Empty)?;
+        Ok((Self::from_yaml(doc).unwrap_or_default(), report))
+    }
+}
+
+session_struct! {
+    /// The track, weather and event
+    pub struct WeekendInfo {
+        "TrackName" => track_name: Option<String>,
+        "TrackID" => track_id: Option<i64>,
+        "TrackLength" => track_length: Option<Distance>,
+        "TrackLengthOfficial" => track_length_official: Option<Distance>,
+        "TrackDisplayName" => track_display_name: Option<String>,
+        "TrackDisplayShortName" => track_display_short_name: Option<String>,
+        "TrackConfigName" => track_config_name: Option<String>,
+        "TrackCity" => track_city: Option<String>,
+        "TrackCountry" => track_country: Option<String>,
+        "TrackAltitude" => track_altitude: Option<Distance>,
+        "TrackNorthOffset" => track_north_offset: Option<Angle>,
+        "TrackNumTurns" => track_num_turns: Option<i32>,
+        "TrackPitSpeedLimit" => track_pit_speed_limit: Option<Speed>,
+        "TrackType" => track_type: Option<String>,
+        "TrackDirection" => track_direction: Option<String>,
+        "TrackWeatherType" => track_weather_type: Option<String>,
+        "TrackSkies" => track_skies: Option<String>,
+        "TrackSurfaceTemp" => track_surface_temp: Option<Temperature>,
+        "TrackAirTemp" => track_air_temp: Option<Temperature>,
+        "TrackAirPressure" => track_air_pressure: Option<Pressure>,
+        "TrackWindVel" => track_wind_vel: Option<Speed>,
+        "TrackWindDir" => track_wind_dir: Option<Angle>,
+        "TrackRelativeHumidity" => track_relative_humidity: Option<Ratio>,
+        "TrackFogLevel" => track_fog_level: Option<Ratio>,
+        "TrackPrecipitation" => track_precipitation: Option<Ratio>,
+        "SeriesID" => series_id: Option<i64>,
+        "SeasonID" => season_id: Option<i64>,
+        "SessionID" => session_id: Option<i64>,
+        "SubSessionID" => sub_session_id: Option<i64>,
+        "LeagueID" => league_id: Option<i64>,
+        "Official" => official: Option<bool>,
+        "RaceWeek" => race_week: Option<i32>,
+        "EventType" => event_type: Option<String>,
+        "Category" => category: Option<String>,
+        "SimMode" => sim_mode: Option<String>,
+        "TeamRacing" => team_racing: Option<bool>,
+        "MinDrivers" => min_drivers: Option<i32>,
+        "MaxDrivers" => max_drivers: Option<i32>,
+        "NumCarClasses" => num_car_classes: Option<i32>,
+        "NumCarTypes" => num_car_types: Option<i32>,
+        "HeatRacing" => heat_racing: Option<bool>,
+        "BuildVersion" => build_version: Option<String>,
+        "WeekendOptions" => weekend_options: WeekendOptions,
+        "TelemetryOptions" => telemetry_options: TelemetryOptions,
+    }
+}
+
+session_struct! {
+    /// Options chosen for the event
+    pub struct WeekendOptions {
+        "NumStarters" => num_starters: Option<i32>,
+        "StartingGrid" => starting_grid: Option<String>,
+        "QualifyScoring" => qualify_scoring: Option<String>,
+        "CourseCautions" => course_cautions: Option<String>,
+        "StandingStart" => standing_start: Option<bool>,
+        "ShortParadeLap" => short_parade_lap: Option<bool>,
+        "Restarts" => restarts: Option<String>,
+        "WeatherType" => weather_type: Option<String>,
+        "Skies" => skies: Option<String>,
+        "WindDirection" => wind_direction: Option<String>,
+        "WindSpeed" => wind_speed: Option<Speed>,
+        "WeatherTemp" => weather_temp: Option<Temperature>,
+        "RelativeHumidity" => relative_humidity: Option<Ratio>,
+        "FogLevel" => fog_level: Option<Ratio>,
+        "TimeOfDay" => time_of_day: Option<String>,
+        "Date" => date: Option<String>,
+        "EarthRotationSpeedupFactor" => earth_rotation_speedup_factor: Option<i32>,
+        "Unofficial" => unofficial: Option<bool>,
+        "CommercialMode" => commercial_mode: Option<String>,
+        "NightMode" => night_mode: Option<String>,
+        "IsFixedSetup" => is_fixed_setup: Option<bool>,
+        "StrictLapsChecking" => strict_laps_checking: Option<String>,
+        "HasOpenRegistration" => has_open_registration: Option<bool>,
+        "HardcoreLevel" => hardcore_level: Option<i32>,
+        "NumJokerLaps" => num_joker_laps: Option<i32>,
+        "IncidentLimit" => incident_limit: Option<Limit<i32>>,
+        "FastRepairsLimit" => fast_repairs_limit: Option<Limit<i32>>,
+        "GreenWhiteCheckeredLimit" => green_white_checkered_limit: Option<Limit<i32>>,
+    }
+}
+
+session_struct! {
+    pub struct TelemetryOptions {
+        "TelemetryDiskFile" => telemetry_disk_file: Option<String>,
+    }
+}
+
+session_struct! {
+    /// The sessions of the event, e.g. practice, qualifying and race
+    pub struct Sessions {
+        "CurrentSessionNum" => current_session_num: Option<i32>,
+        "Sessions" => sessions: Vec<Session>,
+    }
+}
+
+session_struct! {
+    pub struct Session {
+        "SessionNum" => session_num: Option<i32>,
+        "SessionLaps" => session_laps: Option<Limit<i32>>,
+        "SessionTime" => session_time: Option<Limit<Duration>>,
+        "SessionNumLapsToAvg" => session_num_laps_to_avg: Option<i32>,
+        "SessionType" => session_type: Option<String>,
+        "SessionTrackRubberState" => session_track_rubber_state: Option<String>,
+        "SessionName" => session_name: Option<String>,
+        "SessionSubType" => session_sub_type: Option<String>,
+        "SessionSkipped" => session_skipped: Option<bool>,
+        "SessionRunGroupsUsed" => session_run_groups_used: Option<bool>,
+        "ResultsPositions" => results_positions: Vec<ResultPosition>,
+        "ResultsFastestLap" => results_fastest_lap: Vec<FastestLap>,
+        "ResultsAverageLapTime" => results_average_lap_time: Option<f64>,
+        "ResultsNumCautionFlags" => results_num_caution_flags: Option<i32>,
+        "ResultsNumCautionLaps" => results_num_caution_laps: Option<i32>,
+        "ResultsNumLeadChanges" => results_num_lead_changes: Option<i32>,
+        "ResultsLapsComplete" => results_laps_complete: Option<i32>,
+        "ResultsOfficial" => results_official: Option<bool>,
+    }
+}
+
+session_struct! {
+    /// A car's standing in a session
+    pub struct ResultPosition {
+        "Position" => position: Option<i32>,
+        "ClassPosition" => class_position: Option<i32>,
+        "CarIdx" => car_idx: Option<i32>,
+        "Lap" => lap: Option<i32>,
+        "Time" => time: Option<f64>,
+        "FastestLap" => fastest_lap: Option<i32>,
+        "FastestTime" => fastest_time: Option<f64>,
+        "LastTime" => last_time: Option<f64>,
+        "LapsLed" => laps_led: Option<i32>,
+        "LapsComplete" => laps_complete: Option<i32>,
+        "JokerLapsComplete" => joker_laps_complete: Option<i32>,
+        "LapsDriven" => laps_driven: Option<f64>,
+        "Incidents" => incidents: Option<i32>,
+        "ReasonOutId" => reason_out_id: Option<i32>,
+        "ReasonOutStr" => reason_out_str: Option<String>,
+    }
+}
+
+session_struct! {
+    pub struct FastestLap {
+        "CarIdx" => car_idx: Option<i32>,
+        "FastestLap" => fastest_lap: Option<i32>,
+        "FastestTime" => fastest_time: Option<f64>,
+    }
+}
+
+session_struct! {
+    pub struct CameraInfo {
+        "Groups" => groups: Vec<CameraGroup>,
+    }
+}
+
+session_struct! {
+    pub struct CameraGroup {
+        "GroupNum" => group_num: Option<i32>,
+        "GroupName" => group_name: Option<String>,
+        "IsScenic" => is_scenic: Option<bool>,
+        "Cameras" => cameras: Vec<Camera>,
+    }
+}
+
+session_struct! {
+    pub struct Camera {
+        "CameraNum" => camera_num: Option<i32>,
+        "CameraName" => camera_name: Option<String>,
+    }
+}
+
+session_struct! {
+    pub struct RadioInfo {
+        "SelectedRadioNum" => selected_radio_num: Option<i32>,
+        "Radios" => radios: Vec<Radio>,
+    }
+}
+
+session_struct! {
+    pub struct Radio {
+        "RadioNum" => radio_num: Option<i32>,
+        "HopCount" => hop_count: Option<i32>,
+        "NumFrequencies" => num_frequencies: Option<i32>,
+        "TunedToFrequencyNum" => tuned_to_frequency_num: Option<i32>,
+        "ScanningIsOn" => scanning_is_on: Option<bool>,
+        "Frequencies" => frequencies: Vec<Frequency>,
+    }
+}
+
+session_struct! {
+    pub struct Frequency {
+        "FrequencyNum" => frequency_num: Option<i32>,
+        "FrequencyName" => frequency_name: Option<String>,
+        "Priority" => priority: Option<i32>,
+        "CarIdx" => car_idx: Option<i32>,
+        "EntryIdx" => entry_idx: Option<i32>,
+        "ClubID" => club_id: Option<i64>,
+        "CanScan" => can_scan: Option<bool>,
+        "CanSquawk" => can_squawk: Option<bool>,
+        "Muted" => muted: Option<bool>,
+        "IsMutable" => is_mutable: Option<bool>,
+        "IsDeletable" => is_deletable: Option<bool>,
+    }
+}
+
+session_struct! {
+    /// The player's car and every driver in the session
+    pub struct DriverInfo {
+        "DriverCarIdx" => driver_car_idx: Option<i32>,
+        "DriverUserID" => driver_user_id: Option<i64>,
+        "PaceCarIdx" => pace_car_idx: Option<i32>,
+        "DriverCarIdleRPM" => driver_car_idle_rpm: Option<f64>,
+        "DriverCarRedLine" => driver_car_red_line: Option<f64>,
+        "DriverCarEngCylinderCount" => driver_car_eng_cylinder_count: Option<i32>,
+        "DriverCarFuelKgPerLtr" => driver_car_fuel_kg_per_ltr: Option<f64>,
+        "DriverCarFuelMaxLtr" => driver_car_fuel_max_ltr: Option<f64>,
+        "DriverCarMaxFuelPct" => driver_car_max_fuel_pct: Option<f64>,
+        "DriverCarGearNumForward" => driver_car_gear_num_forward: Option<i32>,
+        "DriverCarSLFirstRPM" => driver_car_sl_first_rpm: Option<f64>,
+        "DriverCarSLShiftRPM" => driver_car_sl_shift_rpm: Option<f64>,
+        "DriverCarSLLastRPM" => driver_car_sl_last_rpm: Option<f64>,
+        "DriverCarSLBlinkRPM" => driver_car_sl_blink_rpm: Option<f64>,
+        "DriverCarVersion" => driver_car_version: Option<String>,
+        "DriverPitTrkPct" => driver_pit_trk_pct: Option<f64>,
+        "DriverCarEstLapTime" => driver_car_est_lap_time: Option<f64>,
+        "DriverSetupName" => driver_setup_name: Option<String>,
+        "DriverSetupIsModified" => driver_setup_is_modified: Option<bool>,
+        "DriverSetupLoadTypeName" => driver_setup_load_type_name: Option<String>,
+        "DriverSetupPassedTech" => driver_setup_passed_tech: Option<bool>,
+        "DriverIncidentCount" => driver_incident_count: Option<i32>,
+        "Drivers" => drivers: Vec<Driver>,
+    }
+}
+
+session_struct! {
+    pub struct Driver {
+        "CarIdx" => car_idx: Option<i32>,
+        "UserName" => user_name: Option<String>,
+        "AbbrevName" => abbrev_name: Option<String>,
+        "Initials" => initials: Option<String>,
+        "UserID" => user_id: Option<i64>,
+        "TeamID" => team_id: Option<i64>,
+        "TeamName" => team_name: Option<String>,
+        "CarNumber" => car_number: Option<String>,
+        "CarNumberRaw" => car_number_raw: Option<i32>,
+        "CarPath" => car_path: Option<String>,
+        "CarClassID" => car_class_id: Option<i64>,
+        "CarID" => car_id: Option<i64>,
+        "CarIsPaceCar" => car_is_pace_car: Option<bool>,
+        "CarIsAI" => car_is_ai: Option<bool>,
+        "CarIsElectric" => car_is_electric: Option<bool>,
+        "CarScreenName" => car_screen_name: Option<String>,
+        "CarScreenNameShort" => car_screen_name_short: Option<String>,
+        "CarClassShortName" => car_class_short_name: Option<String>,
+        "CarClassRelSpeed" => car_class_rel_speed: Option<i32>,
+        "CarClassLicenseLevel" => car_class_license_level: Option<i32>,
+        "CarClassWeightPenalty" => car_class_weight_penalty: Option<Mass>,
+        "CarClassColor" => car_class_color: Option<u32>,
+        "CarClassEstLapTime" => car_class_est_lap_time: Option<f64>,
+        "IRating" => i_rating: Option<i32>,
+        "LicLevel" => lic_level: Option<i32>,
+        "LicSubLevel" => lic_sub_level: Option<i32>,
+        "LicString" => lic_string: Option<String>,
+        "LicColor" => lic_color: Option<u32>,
+        "IsSpectator" => is_spectator: Option<bool>,
+        "CarDesignStr" => car_design_str: Option<String>,
+        "HelmetDesignStr" => helmet_design_str: Option<String>,
+        "SuitDesignStr" => suit_design_str: Option<String>,
+        "CarNumberDesignStr" => car_number_design_str: Option<String>,
+        "ClubName" => club_name: Option<String>,
+        "ClubID" => club_id: Option<i64>,
+        "DivisionName" => division_name: Option<String>,
+        "DivisionID" => division_id: Option<i64>,
+        "CurDriverIncidentCount" => cur_driver_incident_count: Option<i32>,
+        "TeamIncidentCount" => team_incident_count: Option<i32>,
+    }
+}
+
+session_struct! {
+    /// How the lap is split into sectors
+    pub struct SplitTimeInfo {
+        "Sectors" => sectors: Vec<Sector>,
+    }
+}
+
+session_struct! {
+    pub struct Sector {
+        "SectorNum" => sector_num: Option<i32>,
+        /// Lap distance at which the sector starts, from 0 to 1
+        "SectorStartPct" => sector_start_pct: Option<f64>,
+    }
+}
+
+/// The player's car setup
+///
+/// Setups differ between cars, so they're kept as a tree of named values.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CarSetup {
+    pub update_count: Option<i32>,
+    pub values: IndexMap<String, SetupValue>,
+}
+
+impl CarSetup {
+    /// Look up a value by its path, e.g. `["Tires", "LeftFront", "ColdPressure"]`
+    pub fn get(&self, path: &[&str]) -> Option<&SetupValue> {
+        let (first, rest) = path.split_first()?;
+        rest.iter()
+            .try_fold(self.values.get(*first)?, |value, key| match value {
+                SetupValue::Group(group) => group.get(*key),
+                _ => None,
+            })
+    }
+}
+
+impl FromYaml for CarSetup {
+    fn from_yaml(yaml: &YamlOwned) -> Option<Self> {
+        let mut values = IndexMap::<String, SetupValue>::from_yaml(yaml)?;
+        let update_count = values.shift_remove("UpdateCount").and_then(|v| match v {
+            SetupValue::Quantity(q) if q.unit.is_empty() => Some(q.value as i32),
+            _ => None,
+        });
+        Some(Self {
+            update_count,
+            values,
+        })
+    }
+}
+
+/// A value in a [`CarSetup`]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SetupValue {
+    /// A number with an optional unit, like `172.4 kPa` or `3`
+    Quantity(Quantity),
+    /// Anything else, like `100% 100% 100%`
+    Text(String),
+    /// A group of named values
+    Group(IndexMap<String, SetupValue>),
+}
+
+impl FromYaml for SetupValue {
+    fn from_yaml(yaml: &YamlOwned) -> Option<Self> {
+        if yaml.is_mapping() {
+            return IndexMap::from_yaml(yaml).map(Self::Group);
+        }
+        match Quantity::from_yaml(yaml) {
+            Some(q) if !q.unit.contains(char::is_whitespace) => Some(Self::Quantity(q)),
+            _ => String::from_yaml(yaml).map(Self::Text),
+        }
+    }
+}
+
+impl IbtFile {
+    /// Parse the session string into a [`SessionInfo`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session string is not valid YAML.
+    pub fn session_info(&self) -> Result<SessionInfo, SessionInfoError> {
+        SessionInfo::parse(&self.raw_session_data())
+    }
+
+    /// Parse the session string into a [`SessionInfo`], also returning what had to be fixed to
+    /// parse it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session string is not valid YAML, even after sanitizing it.
+    pub fn session_info_with_report(
+        &self,
+    ) -> Result<(SessionInfo, SanitizeReport), SessionInfoError> {
+        SessionInfo::parse_with_report(&self.raw_session_data())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use claims::{assert_ok, assert_some, assert_some_eq};
+
+    use crate::{
+        IbtFile,
+        session::{Distance, Limit, Quantity, Ratio, SessionInfo, SetupValue, Temperature},
+        test_utils::test_ibt,
+    };
+
+    const SESSION: &str = r#"---
+WeekendInfo:
+ TrackName: spa up
+ TrackLength: 6.5 km
+ TrackSurfaceTemp: 77.00 F
+ TrackRelativeHumidity: 55 %
+ SessionID: 12
+ Official: 1
+ SomethingNew: 3
+ WeekendOptions:
+  IncidentLimit: unlimited
+SessionInfo:
+ Sessions:
+ - SessionNum: 0
+   SessionLaps: unlimited
+   SessionTime: 600.0000 sec
+   ResultsPositions:
+   - Position: 1
+     CarIdx: 4
+     FastestTime: 137.5
+DriverInfo:
+ DriverCarIdx: 4
+ Drivers:
+ - CarIdx: 4
+   UserName: 1234
+   CarNumber: "7"
+   CarClassColor: 0xffda59
+SplitTimeInfo:
+ Sectors:
+ - SectorNum: 0
+   SectorStartPct: 0.000000
+ - SectorNum: 1
+   SectorStartPct: 0.5
+CarSetup:
+ UpdateCount: 2
+ Tires:
+  LeftFront:
+   ColdPressure: 172.4 kPa
+   TreadRemaining: 100% 100% 100%
+...
+"#;
+
+    #[test]
+    fn parses_unescaped_names() {
+        let session = SESSION.replace("UserName: 1234", "UserName: *Team: One");
+        let (info, report) = assert_ok!(SessionInfo::parse_with_report(&session));
+        assert_eq!(report.fixes.len(), 1);
+        assert_eq!(report.fixes[0].key, "UserName");
+        assert_some_eq!(
+            info.driver_info.drivers[0].user_name.as_deref(),
+            "*Team: One"
+        );
+    }
+
+    #[test]
+    fn parses_session_info() {
+        let info = assert_ok!(SessionInfo::parse(SESSION));
+
+        let weekend = &info.weekend_info;
+        assert_some_eq!(weekend.track_name.as_deref(), "spa up");
+        assert_some_eq!(weekend.track_length, Distance(6500.0));
+        assert_some_eq!(weekend.track_surface_temp, Temperature(25.0));
+        assert_some_eq!(weekend.track_relative_humidity, Ratio(0.55));
+        assert_some_eq!(weekend.session_id, 12);
+        assert_some_eq!(weekend.official, true);
+        assert_some_eq!(weekend.weekend_options.incident_limit, Limit::Unlimited);
+
+        let session = &info.session_info.sessions[0];
+        assert_some_eq!(session.session_laps, Limit::Unlimited);
+        assert_some_eq!(
+            session.session_time,
+            Limit::Limited(Duration::from_secs(600))
+        );
+        assert_some_eq!(session.results_positions[0].fastest_time, 137.5);
+
+        let driver = &info.driver_info.drivers[0];
+        assert_some_eq!(driver.user_name.as_deref(), "1234");
+        assert_some_eq!(driver.car_number.as_deref(), "7");
+        assert_some_eq!(driver.car_class_color, 0xffda59);
+
+        assert_eq!(info.split_time_info.sectors.len(), 2);
+        assert!(info.camera_info.groups.is_empty());
+
+        let setup = assert_some!(info.car_setup);
+        assert_some_eq!(setup.update_count, 2);
+        assert_some_eq!(
+            setup.get(&["Tires", "LeftFront", "ColdPressure"]),
+            &SetupValue::Quantity(Quantity {
+                value: 172.4,
+                unit: "kPa".to_string()
+            })
+        );
+        assert_some_eq!(
+            setup.get(&["Tires", "LeftFront", "TreadRemaining"]),
+            &SetupValue::Text("100% 100% 100%".to_string())
+        );
+    }
+
+    #[test]
+    fn reads_session_info_from_file() {
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt(&[1.0])));
+        let info = assert_ok!(file.session_info());
+        assert_some_eq!(info.weekend_info.track_name.as_deref(), "test");
+        assert_some_eq!(info.weekend_info.sub_session_id, 2);
+        assert_eq!(info.car_setup, None);
+    }
+}
@@ -0,0 +1,180 @@
+//! Fixing up iRacing's session strings before parsing them as YAML
+//!
+//! iRacing writes values like driver and team names without escaping them, so a name containing
+//! `: ` or starting with `*` makes the whole session string invalid YAML. Others, like a name
+//! containing ` #`, parse but lose part of the value. Like the official SDK tools, we quote such
+//! values before parsing.
+
+use std::borrow::Cow;
+
+/// A value that had to be quoted
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fix {
+    /// Line of the value in the session string, starting at 1
+    pub line: usize,
+    pub key: String,
+    /// The value as written by iRacing
+    pub value: String,
+}
+
+/// Describes what [`sanitize`] had to fix
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SanitizeReport {
+    pub fixes: Vec<Fix>,
+}
+
+impl SanitizeReport {
+    /// Whether the session string was valid as is
+    pub fn is_clean(&self) -> bool {
+        self.fixes.is_empty()
+    }
+}
+
+/// Quote values in a session string that would otherwise be invalid or misread as YAML
+///
+/// Only single-line `Key: value` entries are changed. The string is borrowed if nothing needed
+/// fixing.
+pub fn sanitize(session_string: &str) -> (Cow<'_, str>, SanitizeReport) {
+    let mut report = SanitizeReport::default();
+    let mut out = String::new();
+
+    for (idx, line) in session_string.split_inclusive('\n').enumerate() {
+        let Some((prefix, key, value)) = split_entry(line) else {
+            out.push_str(line);
+            continue;
+        };
+        if !needs_quotes(value) {
+            out.push_str(line);
+            continue;
+        }
+
+        report.fixes.push(Fix {
+            line: idx + 1,
+            key: key.to_string(),
+            value: value.to_string(),
+        });
+        out.push_str(prefix);
+        out.push('\'');
+        out.push_str(&value.replace('\'', "''"));
+        out.push('\'');
+        out.push_str(&line[line.trim_end().len()..]);
+    }
+
+    if report.is_clean() {
+        (Cow::Borrowed(session_string), report)
+    } else {
+        (Cow::Owned(out), report)
+    }
+}
+
+/// Split a `Key: value` line into everything up to the value, the key and the trimmed value
+fn split_entry(line: &str) -> Option<(&str, &str, &str)> {
+    let content = line.trim_start();
+    // entries can be the first item of a sequence
+    let content = content.strip_prefix("- ").unwrap_or(content).trim_start();
+
+    let (key, rest) = content.split_once(": ")?;
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+    let value = rest.trim();
+    if value.is_empty() {
+        return None;
+    }
+    let value_start = line.len() - rest.trim_start().len();
+    Some((&line[..value_start], key, value))
+}
+
+fn needs_quotes(value: &str) -> bool {
+    let Some(first) = value.chars().next() else {
+        return false;
+    };
+    match first {
+        '"' => !is_double_quoted(value),
+        '\'' => !is_single_quoted(value),
+        // aliases, anchors, tags, comments, block scalars, flow collections and reserved
+        // indicators can't start a plain value
+        '*' | '&' | '!' | '#' | '|' | '>' | '[' | ']' | '{' | '}' | ',' | '@' | '`' | '%' => true,
+        '-' | '?' | ':' => value.len() == 1 || value[1..].starts_with(' '),
+        _ => value.contains(": ") || value.ends_with(':') || value.contains(" #"),
+    }
+}
+
+fn is_double_quoted(value: &str) -> bool {
+    let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) else {
+        return false;
+    };
+    let mut escaped = false;
+    for c in inner.chars() {
+        match (escaped, c) {
+            (false, '\\') => escaped = true,
+            (false, '"') => return false,
+            _ => escaped = false,
+        }
+    }
+    !escaped
+}
+
+fn is_single_quoted(value: &str) -> bool {
+    value
+        .strip_prefix('\'')
+        .and_then(|v| v.strip_suffix('\''))
+        .is_some_and(|inner| inner.replace("''", "").find('\'').is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_ok;
+    use saphyr::{LoadableYamlNode, YamlOwned};
+
+    use crate::session::sanitize::{Fix, sanitize};
+
+    #[test]
+    fn leaves_valid_strings_alone() {
+        let session = "---\nWeekendInfo:\n TrackName: spa up\n CarNumber: \"7\"\n...\n";
+        let (fixed, report) = sanitize(session);
+        assert!(report.is_clean());
+        assert_eq!(fixed, session);
+    }
+
+    #[test]
+    fn quotes_malformed_values() {
+        let session = "---\nDriverInfo:\n Drivers:\n - CarIdx: 0\n   UserName: *Star\n   TeamName: Team: Fast #1\n - CarIdx: 1\n   UserName: Bob \"The Builder\n   TeamName: \"Quoted\" Racing\n   AbbrevName: O'Neil\n...\n";
+        assert!(YamlOwned::load_from_str(session).is_err());
+
+        let (fixed, report) = sanitize(session);
+        assert_eq!(
+            report.fixes,
+            [
+                Fix {
+                    line: 5,
+                    key: "UserName".to_string(),
+                    value: "*Star".to_string()
+                },
+                Fix {
+                    line: 6,
+                    key: "TeamName".to_string(),
+                    value: "Team: Fast #1".to_string()
+                },
+                Fix {
+                    line: 9,
+                    key: "TeamName".to_string(),
+                    value: "\"Quoted\" Racing".to_string()
+                },
+            ]
+        );
+
+        let docs = assert_ok!(YamlOwned::load_from_str(&fixed));
+        let drivers = docs[0]
+            .as_mapping_get("DriverInfo")
+            .and_then(|d| d.as_mapping_get("Drivers"))
+            .and_then(|d| d.as_sequence())
+            .unwrap();
+        let name = |idx: usize, key| drivers[idx].as_mapping_get(key).and_then(|v| v.as_str());
+        assert_eq!(name(0, "UserName"), Some("*Star"));
+        assert_eq!(name(0, "TeamName"), Some("Team: Fast #1"));
+        assert_eq!(name(1, "UserName"), Some("Bob \"The Builder"));
+        assert_eq!(name(1, "TeamName"), Some("\"Quoted\" Racing"));
+        assert_eq!(name(1, "AbbrevName"), Some("O'Neil"));
+    }
+}
@@ -0,0 +1,178 @@
+//! Measurements with units, like `3.21 km` or `25.00 C`
+//!
+//! Each type stores its value in a fixed base unit, converting from whichever unit the session
+//! string used.
+
+use saphyr::YamlOwned;
+
+use crate::session::{FromYaml, yaml::split_unit};
+
+/// Defines a newtype over an `f64` in the given base unit, read from values in any of the listed
+/// units
+macro_rules! unit {
+    (
+        $(#[$meta:meta])*
+        $name:ident {
+            $($unit:literal => |$value:ident| $to_base:expr),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub struct $name(pub f64);
+
+        impl FromYaml for $name {
+            /// Plain numbers are taken to be in the base unit
+            fn from_yaml(yaml: &YamlOwned) -> Option<Self> {
+                let (value, unit) = split_unit(yaml)?;
+                match unit {
+                    "" => Some(Self(value)),
+                    $($unit => {
+                        let $value = value;
+                        Some(Self($to_base))
+                    })+
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+unit! {
+    /// A distance in meters
+    Distance {
+        "m" => |v| v,
+        "km" => |v| v * 1000.0,
+        "cm" => |v| v / 100.0,
+        "mm" => |v| v / 1000.0,
+        "mi" => |v| v * 1609.344,
+        "ft" => |v| v * 0.3048,
+        "in" => |v| v * 0.0254,
+    }
+}
+
+unit! {
+    /// A speed in meters per second
+    Speed {
+        "m/s" => |v| v,
+        "kph" => |v| v / 3.6,
+        "km/h" => |v| v / 3.6,
+        "mph" => |v| v * 0.447_04,
+    }
+}
+
+unit! {
+    /// A temperature in degrees Celsius
+    Temperature {
+        "C" => |v| v,
+        "F" => |v| (v - 32.0) * 5.0 / 9.0,
+    }
+}
+
+unit! {
+    /// A pressure in kilopascals
+    Pressure {
+        "kPa" => |v| v,
+        "Pa" => |v| v / 1000.0,
+        "mb" => |v| v / 10.0,
+        "hPa" => |v| v / 10.0,
+        "bar" => |v| v * 100.0,
+        "psi" => |v| v * 6.894_757,
+        "Hg" => |v| v * 3.386_389,
+    }
+}
+
+unit! {
+    /// An angle in radians
+    Angle {
+        "rad" => |v| v,
+        "deg" => |v| v.to_radians(),
+    }
+}
+
+unit! {
+    /// A mass in kilograms
+    Mass {
+        "kg" => |v| v,
+        "lb" => |v| v * 0.453_592_37,
+    }
+}
+
+unit! {
+    /// A volume in liters
+    Volume {
+        "l" => |v| v,
+        "L" => |v| v,
+        "gal" => |v| v * 3.785_411_784,
+    }
+}
+
+unit! {
+    /// A fraction, where `1.0` is 100%
+    Ratio {
+        "%" => |v| v / 100.0,
+    }
+}
+
+impl Distance {
+    pub fn kilometers(&self) -> f64 {
+        self.0 / 1000.0
+    }
+
+    pub fn miles(&self) -> f64 {
+        self.0 / 1609.344
+    }
+}
+
+impl Speed {
+    pub fn kph(&self) -> f64 {
+        self.0 * 3.6
+    }
+
+    pub fn mph(&self) -> f64 {
+        self.0 / 0.447_04
+    }
+}
+
+impl Temperature {
+    pub fn fahrenheit(&self) -> f64 {
+        self.0 * 9.0 / 5.0 + 32.0
+    }
+}
+
+/// A limit on a session, like its number of laps, which may be `unlimited`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Limit<T> {
+    #[default]
+    Unlimited,
+    Limited(T),
+}
+
+impl<T: FromYaml> FromYaml for Limit<T> {
+    fn from_yaml(yaml: &YamlOwned) -> Option<Self> {
+        if yaml.as_str() == Some("unlimited") {
+            Some(Self::Unlimited)
+        } else {
+            T::from_yaml(yaml).map(Self::Limited)
+        }
+    }
+}
+
+/// A number with a unit this crate doesn't convert, as found in car setups
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: String,
+}
+
+impl FromYaml for Quantity {
+    fn from_yaml(yaml: &YamlOwned) -> Option<Self> {
+        let (value, unit) = split_unit(yaml)?;
+        Some(Self {
+            value,
+            unit: unit.to_string(),
+        })
+    }
+}
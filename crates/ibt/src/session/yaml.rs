@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use indexmap::IndexMap;
+use saphyr::YamlOwned;
+
+/// A type that can be read from a node of the session string
+///
+/// Reading is lenient: a node with an unexpected shape yields `None` rather than an error, and
+/// mapping keys that no field asks for are ignored.
+pub trait FromYaml: Sized {
+    fn from_yaml(yaml: &YamlOwned) -> Option<Self>;
+}
+
+/// Defines a struct read from a YAML mapping, with each field read from the given key
+///
+/// Fields that are missing or can't be read are left at their default value, so field types
+/// should be `Option`s, `Vec`s, or other structs defined with this macro.
+macro_rules! session_struct {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $key:literal => $field:ident: $ty:ty,
+            )+
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, Default, PartialEq)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub struct $name {
+            $(
+                $(#[$field_meta])*
+                pub $field: $ty,
+            )+
+        }
+
+        impl $crate::session::FromYaml for $name {
+            fn from_yaml(yaml: &::saphyr::YamlOwned) -> Option<Self> {
+                yaml.as_mapping()?;
+                Some(Self {
+                    $(
+                        $field: yaml
+                            .as_mapping_get($key)
+                            .and_then($crate::session::FromYaml::from_yaml)
+                            .unwrap_or_default(),
+                    )+
+                })
+            }
+        }
+    };
+}
+
+pub(crate) use session_struct;
+
+impl<T: FromYaml> FromYaml for Option<T> {
+    fn from_yaml(yaml: &YamlOwned) -> Option<Self> {
+        Some(T::from_yaml(yaml))
+    }
+}
+
+impl<T: FromYaml> FromYaml for Vec<T> {
+    /// Items that can't be read are skipped
+    fn from_yaml(yaml: &YamlOwned) -> Option<Self> {
+        Some(
+            yaml.as_sequence()?
+                .iter()
+                .filter_map(T::from_yaml)
+                .collect(),
+        )
+    }
+}
+
+impl FromYaml for String {
+    /// Numbers and booleans are accepted too, as names like `TeamName: 1234` aren't quoted
+    fn from_yaml(yaml: &YamlOwned) -> Option<Self> {
+        if let Some(s) = yaml.as_str() {
+            Some(s.to_string())
+        } else if let Some(i) = yaml.as_integer() {
+            Some(i.to_string())
+        } else if let Some(f) = yaml.as_floating_point() {
+            Some(f.to_string())
+        } else {
+            yaml.as_bool().map(|b| b.to_string())
+        }
+    }
+}
+
+impl FromYaml for bool {
+    /// iRacing writes most flags as `0` or `1`
+    fn from_yaml(yaml: &YamlOwned) -> Option<Self> {
+        yaml.as_bool().or_else(|| yaml.as_integer().map(|i| i != 0))
+    }
+}
+
+impl FromYaml for f64 {
+    fn from_yaml(yaml: &YamlOwned) -> Option<Self> {
+        yaml.as_floating_point()
+            .or_else(|| yaml.as_integer().map(|i| i as f64))
+    }
+}
+
+macro_rules! impl_from_yaml_int {
+    ($($t:ty),+) => {
+        $(
+            impl FromYaml for $t {
+                fn from_yaml(yaml: &YamlOwned) -> Option<Self> {
+                    yaml.as_integer()?.try_into().ok()
+                }
+            }
+        )+
+    };
+}
+
+impl_from_yaml_int!(i32, i64, u32);
+
+impl FromYaml for Duration {
+    /// A number of seconds like `600.0000 sec`
+    fn from_yaml(yaml: &YamlOwned) -> Option<Self> {
+        let (value, unit) = split_unit(yaml)?;
+        match unit {
+            "" | "s" | "sec" => Duration::try_from_secs_f64(value).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl<T: FromYaml> FromYaml for IndexMap<String, T> {
+    /// Entries that can't be read are skipped
+    fn from_yaml(yaml: &YamlOwned) -> Option<Self> {
+        Some(
+            yaml.as_mapping()?
+                .iter()
+                .filter_map(|(k, v)| Some((String::from_yaml(k)?, T::from_yaml(v)?)))
+                .collect(),
+        )
+    }
+}
+
+/// Split a value like `3.21 km` or `55%` into its number and unit
+///
+/// Plain numbers have an empty unit.
+pub(crate) fn split_unit(yaml: &YamlOwned) -> Option<(f64, &str)> {
+    if let Some(value) = f64::from_yaml(yaml) {
+        return Some((value, ""));
+    }
+    let s = yaml.as_str()?.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+')))
+        .unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    Some((value.parse().ok()?, unit.trim()))
+}
@@ -0,0 +1,200 @@
+//! Cutting a file down to a smaller selection of samples
+
+use std::{
+    io::{Seek, Write},
+    ops::{Range, RangeInclusive},
+};
+
+use crate::{
+    IbtFile, IbtWriter, IbtWriterError,
+    telemetry::{Sample, Value},
+};
+
+/// Which samples of a file to keep
+#[derive(Clone, Debug, PartialEq)]
+pub enum Selection {
+    /// Samples with the given indices
+    Samples(Range<usize>),
+    /// Samples with a `SessionTime` in the given range, in seconds
+    SessionTime(Range<f64>),
+    /// Samples with a `Lap` in the given range
+    Laps(RangeInclusive<i32>),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SliceError {
+    #[error(transparent)]
+    Write(#[from] IbtWriterError),
+
+    /// The selection needs a var that the file doesn't have
+    #[error("file has no `{0}` var")]
+    MissingVar(&'static str),
+
+    /// The selection's sample indices lie outside of the file
+    #[error("samples `{start}..{end}` are out of range, there are `{count}` samples")]
+    OutOfRange {
+        start: usize,
+        end: usize,
+        count: usize,
+    },
+
+    /// No samples match the selection
+    #[error("no samples match the selection")]
+    Empty,
+}
+
+impl IbtFile {
+    /// Find the range of sample indices covered by a selection
+    ///
+    /// For time and lap selections, the range runs from the first to the last matching sample.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no samples match, the selection is out of range, or the file lacks
+    /// the var the selection needs.
+    pub fn select(&self, selection: &Selection) -> Result<Range<usize>, SliceError> {
+        let range = match selection {
+            Selection::Samples(range) => {
+                if range.end > self.sample_count() {
+                    return Err(SliceError::OutOfRange {
+                        start: range.start,
+                        end: range.end,
+                        count: self.sample_count(),
+                    });
+                }
+                range.clone()
+            }
+            Selection::SessionTime(times) => self.select_matching("SessionTime", |v| match v {
+                Value::Double(t) => times.contains(&t),
+                _ => false,
+            })?,
+            Selection::Laps(laps) => self.select_matching("Lap", |v| match v {
+                Value::Int(lap) => laps.contains(&lap),
+                _ => false,
+            })?,
+        };
+
+        if range.is_empty() {
+            return Err(SliceError::Empty);
+        }
+        Ok(range)
+    }
+
+    fn select_matching(
+        &self,
+        var_name: &'static str,
+        matches: impl Fn(Value) -> bool,
+    ) -> Result<Range<usize>, SliceError> {
+        let var = self
+            .vars
+            .var(var_name)
+            .ok_or(SliceError::MissingVar(var_name))?;
+        let is_match = |s: &Sample<'_>| matches(s.read_var(var));
+
+        let Some(first) = self.samples().position(|s| is_match(&s)) else {
+            return Ok(0..0);
+        };
+        let last = (first..self.sample_count())
+            .rev()
+            .find(|idx| is_match(&self.sample(*idx)))
+            .unwrap_or(first);
+        Ok(first..last + 1)
+    }
+
+    /// Write the selected samples to a new IBT file
+    ///
+    /// The new file has the same vars and session string as this one. Its disk sub-header is
+    /// recomputed from the selected samples, see [`IbtWriter::disk_sub_header`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the selection is invalid or an IO error occurs.
+    pub fn write_slice<W: Write + Seek>(
+        &self,
+        selection: &Selection,
+        out: W,
+    ) -> Result<W, SliceError> {
+        let range = self.select(selection)?;
+
+        let mut writer = IbtWriter::new(out, &self.header, &self.vars, self.session_bytes())?;
+        for idx in range {
+            writer.write_sample(self.sample(idx).as_bytes())?;
+        }
+        Ok(writer.finish(self.disk_sub_header.date)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, time::Duration};
+
+    use claims::{assert_matches, assert_ok, assert_ok_eq};
+
+    use crate::{
+        IbtFile, IbtWriter,
+        slice::{Selection, SliceError},
+        test_utils::test_ibt,
+    };
+
+    /// A file with two samples per second, starting on lap 1 and incrementing `Lap` every second
+    fn test_file() -> IbtFile {
+        let times: Vec<_> = (0..8).map(|i| f64::from(i) / 2.0).collect();
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt(&times)));
+
+        let mut writer = assert_ok!(IbtWriter::new(
+            Cursor::new(Vec::new()),
+            &file.header,
+            &file.vars,
+            file.session_bytes(),
+        ));
+        for (i, sample) in file.samples().enumerate() {
+            let mut bytes = sample.as_bytes().to_vec();
+            bytes[12..16].copy_from_slice(&(i as i32 / 2 + 1).to_le_bytes());
+            assert_ok!(writer.write_sample(&bytes));
+        }
+        let out = assert_ok!(writer.finish(file.disk_sub_header.date));
+        assert_ok!(IbtFile::from_bytes(&out.into_inner()))
+    }
+
+    fn slice(file: &IbtFile, selection: &Selection) -> IbtFile {
+        let out = assert_ok!(file.write_slice(selection, Cursor::new(Vec::new())));
+        assert_ok!(IbtFile::from_bytes(&out.into_inner()))
+    }
+
+    #[test]
+    fn selects_samples() {
+        let file = test_file();
+
+        assert_ok_eq!(file.select(&Selection::Samples(2..5)), 2..5);
+        assert_ok_eq!(file.select(&Selection::SessionTime(1.0..2.5)), 2..5);
+        assert_ok_eq!(file.select(&Selection::Laps(2..=3)), 2..6);
+
+        assert_matches!(
+            file.select(&Selection::Samples(6..9)),
+            Err(SliceError::OutOfRange { count: 8, .. })
+        );
+        assert_matches!(
+            file.select(&Selection::Laps(10..=12)),
+            Err(SliceError::Empty)
+        );
+    }
+
+    #[test]
+    fn writes_slice_by_lap() {
+        let file = test_file();
+        let sliced = slice(&file, &Selection::Laps(2..=3));
+
+        assert_eq!(sliced.sample_count(), 4);
+        assert_eq!(sliced.disk_sub_header.date, file.disk_sub_header.date);
+        assert_eq!(sliced.disk_sub_header.start_time, Duration::from_secs(1));
+        assert_eq!(
+            sliced.disk_sub_header.end_time,
+            Duration::from_secs_f64(2.5)
+        );
+        assert_eq!(sliced.disk_sub_header.lap_count, 2);
+        assert_eq!(sliced.session_bytes(), file.session_bytes());
+        for (idx, sample) in sliced.samples().enumerate() {
+            assert_eq!(sample.as_bytes(), file.sample(idx + 2).as_bytes());
+        }
+    }
+}
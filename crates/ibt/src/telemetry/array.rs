@@ -0,0 +1,161 @@
+use std::{fmt, marker::PhantomData, slice::ChunksExact};
+
+use crate::telemetry::{TypedReadError, VarHeader, VarValue, typed::check_type};
+
+/// A borrowed view of all values of an array var in a [`Sample`][crate::telemetry::Sample]
+///
+/// Values are decoded from the sample's bytes as they are accessed, so creating and iterating over
+/// a view never allocates. Elements don't need to be aligned.
+#[derive(Clone, Copy)]
+pub struct ArrayView<'data, T> {
+    bytes: &'data [u8],
+    /// Size in bytes of each element
+    size: usize,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<'data, T: VarValue> ArrayView<'data, T> {
+    pub(crate) fn new(bytes: &'data [u8], size: usize) -> Self {
+        Self {
+            bytes,
+            size,
+            _type: PhantomData,
+        }
+    }
+
+    /// Number of values in the array
+    pub fn len(&self) -> usize {
+        self.bytes.len() / self.size
+    }
+
+    /// Whether the array has no values
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Get the value at `idx`, or `None` if it is out of bounds
+    pub fn get(&self, idx: usize) -> Option<T> {
+        let start = idx.checked_mul(self.size)?;
+        let bytes = self.bytes.get(start..start + self.size)?;
+        Some(T::from_bytes(bytes))
+    }
+
+    /// Iterate over the values in the array
+    pub fn iter(&self) -> ArrayIter<'data, T> {
+        ArrayIter {
+            chunks: self.bytes.chunks_exact(self.size),
+            _type: PhantomData,
+        }
+    }
+
+    /// Copy the values into a `Vec`
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter().collect()
+    }
+}
+
+impl<'data, T: VarValue> IntoIterator for ArrayView<'data, T> {
+    type Item = T;
+    type IntoIter = ArrayIter<'data, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'data, T: VarValue> IntoIterator for &ArrayView<'data, T> {
+    type Item = T;
+    type IntoIter = ArrayIter<'data, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: VarValue + fmt::Debug> fmt::Debug for ArrayView<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the values of an [`ArrayView`]
+#[derive(Clone, Debug)]
+pub struct ArrayIter<'data, T> {
+    chunks: ChunksExact<'data, u8>,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T: VarValue> Iterator for ArrayIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.chunks.next().map(T::from_bytes)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+impl<T: VarValue> DoubleEndedIterator for ArrayIter<'_, T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.chunks.next_back().map(T::from_bytes)
+    }
+}
+
+impl<T: VarValue> ExactSizeIterator for ArrayIter<'_, T> {}
+
+/// A handle to an array var whose type has already been checked
+///
+/// The array counterpart to [`TypedVar`][crate::telemetry::TypedVar]. Obtained from
+/// [`VarSet::typed_array`][crate::telemetry::VarSet::typed_array] or [`TypedArrayVar::new`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TypedArrayVar<T> {
+    pub(crate) offset: usize,
+    pub(crate) size: usize,
+    pub(crate) count: usize,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T: VarValue> TypedArrayVar<T> {
+    /// Check that the values of `var` can be read as `T`
+    ///
+    /// Scalar vars are treated as arrays of one value.
+    pub fn new(var: &VarHeader) -> Result<Self, TypedReadError> {
+        check_type::<T>(var)?;
+        Ok(Self {
+            offset: var.offset,
+            size: var.ty.size(),
+            count: var.count,
+            _type: PhantomData,
+        })
+    }
+
+    /// Number of values in the array
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_none, assert_some_eq};
+
+    use crate::telemetry::ArrayView;
+
+    #[test]
+    fn reads_unaligned_values() {
+        let values = [1.5_f32, -2.0, 3.25];
+        let mut bytes = vec![0];
+        bytes.extend(values.iter().flat_map(|v| v.to_le_bytes()));
+
+        // skip the first byte so elements are not 4-byte aligned
+        let view = ArrayView::<f32>::new(&bytes[1..], 4);
+        assert_eq!(view.len(), 3);
+        assert_some_eq!(view.get(1), -2.0);
+        assert_none!(view.get(3));
+        assert_eq!(view.to_vec(), values);
+        assert_eq!(view.iter().rev().collect::<Vec<_>>(), [3.25, -2.0, 1.5]);
+        assert_eq!(format!("{view:?}"), "[1.5, -2.0, 3.25]");
+    }
+}
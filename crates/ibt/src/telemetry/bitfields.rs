@@ -1,10 +1,18 @@
 //! Bitfield types
 
+use std::borrow::Cow;
+
+use crate::{
+    aligned::align_cast,
+    telemetry::{VarHeader, VarType, VarValue},
+};
+
 /// Multiple related telemetry flags compressed into one value
 ///
 /// Internally, these values are 32 bit integers, where each binary bit may represent the state of
 /// a certain flag. See each variant's internal type for the possible values.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Bitfield {
     EngineWarnings(EngineWarnings),
     Flags(Flags),
@@ -28,6 +36,32 @@ impl Bitfield {
         }
     }
 
+    /// The raw value
+    pub fn bits(&self) -> u32 {
+        match self {
+            Self::EngineWarnings(b) => b.bits(),
+            Self::Flags(b) => b.bits(),
+            Self::CameraState(b) => b.bits(),
+            Self::PitServiceFlags(b) => b.bits(),
+            Self::PaceFlags(b) => b.bits(),
+            Self::Unknown(raw) => *raw,
+        }
+    }
+
+    /// Names of the set flags, in bit order, with bits that have no name as `bitN`
+    ///
+    /// Empty for [`Bitfield::Unknown`].
+    pub fn flag_names(&self) -> Vec<Cow<'static, str>> {
+        match self {
+            Self::EngineWarnings(b) => b.flag_names().collect(),
+            Self::Flags(b) => b.flag_names().collect(),
+            Self::CameraState(b) => b.flag_names().collect(),
+            Self::PitServiceFlags(b) => b.flag_names().collect(),
+            Self::PaceFlags(b) => b.flag_names().collect(),
+            Self::Unknown(_) => Vec::new(),
+        }
+    }
+
     fn engine_warnings(raw: u32) -> Self {
         Self::EngineWarnings(EngineWarnings(raw))
     }
@@ -51,34 +85,88 @@ impl Bitfield {
 
 /// Defines a tuple struct holding a `u32`, where each bit in the value may represent a different
 /// "flag". An accessor for each flag is generated, which masks the value to extract the flag's
-/// specified bit, returning `true` if the bit is set. A `Debug` impl is also generated.
+/// specified bit, returning `true` if the bit is set. A `Debug` impl listing the set flags is also
+/// generated, as well as a [`VarValue`] impl for vars with the given unit. Bits that have no name
+/// are named `bitN`. With the `serde` feature, the struct is (de)serialized as a set of flag
+/// names.
 macro_rules! bitfield {
-    ($name:ident { $($bit:literal => $field:ident),+ $(,)? }) => {
-        #[derive(Clone, Copy)]
+    ($name:ident: $unit:literal { $($bit:literal => $field:ident),+ $(,)? }) => {
+        #[derive(Clone, Copy, PartialEq, Eq)]
         pub struct $name(u32);
 
+        impl VarValue for $name {
+            fn is_compatible(var: &VarHeader) -> bool {
+                var.ty == VarType::Bitfield && var.unit == $unit
+            }
+
+            fn from_bytes(bytes: &[u8]) -> Self {
+                Self(align_cast(bytes))
+            }
+        }
+
         impl $name {
             $(
                 pub fn $field(&self) -> bool {
                     self.0 & (1 <<  $bit) > 0
                 }
             )+
+
+            /// The raw value
+            pub fn bits(&self) -> u32 {
+                self.0
+            }
+
+            pub fn from_bits(bits: u32) -> Self {
+                Self(bits)
+            }
+
+            /// Each set flag as a value of its own, in bit order
+            pub fn each_flag(&self) -> impl Iterator<Item = Self> {
+                ::bit_iter::BitIter::from(self.0).map(|set_bit| Self(1 << set_bit))
+            }
+
+            /// Names of the set flags, in bit order, with bits that have no name as `bitN`
+            pub fn flag_names(&self) -> impl Iterator<Item = Cow<'static, str>> {
+                ::bit_iter::BitIter::from(self.0).map(|set_bit| match set_bit {
+                    $($bit => Cow::Borrowed(stringify!($field)),)+
+                    _ => Cow::Owned(format!("bit{set_bit}")),
+                })
+            }
         }
 
         impl ::core::fmt::Debug for $name {
             fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> ::core::fmt::Result {
-                let entries = ::bit_iter::BitIter::from(self.0).map(|set_bit| match set_bit {
-                    $($bit => stringify!($field),)+
-                    _ => "unknown",
-                });
-                f.debug_set().entries(entries).finish()
+                f.debug_set().entries(self.flag_names()).finish()
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl ::serde::Serialize for $name {
+            fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq(self.flag_names())
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> ::serde::Deserialize<'de> for $name {
+            fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                const FLAGS: &[&str] = &[$(stringify!($field)),+];
+
+                let names = <Vec<String> as ::serde::Deserialize>::deserialize(deserializer)?;
+                names.iter().try_fold(Self(0), |bits, name| match name.as_str() {
+                    $(stringify!($field) => Ok(Self(bits.0 | (1 << $bit))),)+
+                    _ => match name.strip_prefix("bit").and_then(|bit| bit.parse::<u32>().ok()) {
+                        Some(bit) if bit < 32 => Ok(Self(bits.0 | (1 << bit))),
+                        _ => Err(<D::Error as ::serde::de::Error>::unknown_variant(name, FLAGS)),
+                    },
+                })
             }
         }
     };
 }
 
 bitfield! {
-    EngineWarnings {
+    EngineWarnings: "irsdk_EngineWarnings" {
         0 => water_temp,
         1 => fuel_pressure,
         2 => oil_pressure,
@@ -92,7 +180,7 @@ bitfield! {
 }
 
 bitfield! {
-    Flags {
+    Flags: "irsdk_Flags" {
         0 => checkered,
         1 => white,
         2 => green,
@@ -125,7 +213,7 @@ bitfield! {
 }
 
 bitfield! {
-    CameraState {
+    CameraState: "irsdk_CameraState" {
         0 => is_session_screen,
         1 => is_scenic_active,
         3 => camera_tool_active,
@@ -139,7 +227,7 @@ bitfield! {
 }
 
 bitfield! {
-    PitServiceFlags {
+    PitServiceFlags: "irsdk_PitSvFlags" {
         0 => lf_tire_change,
         1 => rf_tire_change,
         2 => lr_tire_change,
@@ -151,7 +239,7 @@ bitfield! {
 }
 
 bitfield! {
-    PaceFlags {
+    PaceFlags: "irsdk_PaceFlags" {
         0 => end_of_line,
         1 => free_pass,
         2 => waved_around,
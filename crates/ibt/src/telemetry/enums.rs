@@ -1,13 +1,19 @@
 //! Enum types
 
+use std::fmt;
+
 use num_enum::FromPrimitive;
 
-use crate::aligned::align_cast;
+use crate::{
+    aligned::align_cast,
+    telemetry::{VarHeader, VarType, VarValue},
+};
 
 /// A value representing one of several states
 ///
 /// These are 32 bit integers under the hood, but translated to their actual meaning.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Enum {
     TrackLocation(TrackLocation),
     TrackSurface(TrackSurface),
@@ -18,6 +24,21 @@ pub enum Enum {
     TrackWetness(TrackWetness),
 }
 
+impl fmt::Display for Enum {
+    /// Writes the name of the inner value, e.g. `OnTrack`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TrackLocation(v) => write!(f, "{v:?}"),
+            Self::TrackSurface(v) => write!(f, "{v:?}"),
+            Self::SessionState(v) => write!(f, "{v:?}"),
+            Self::CarLeftRight(v) => write!(f, "{v:?}"),
+            Self::PitServiceStatus(v) => write!(f, "{v:?}"),
+            Self::PaceMode(v) => write!(f, "{v:?}"),
+            Self::TrackWetness(v) => write!(f, "{v:?}"),
+        }
+    }
+}
+
 impl Enum {
     pub(crate) fn parse(slice: &[u8], unit: &str) -> Option<Self> {
         let e = match unit {
@@ -64,6 +85,7 @@ impl Enum {
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(i32)]
 pub enum TrackLocation {
     NotInWorld = -1,
@@ -79,6 +101,7 @@ pub enum TrackLocation {
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(i32)]
 pub enum TrackSurface {
     SurfaceNotInWorld = -1,
@@ -116,6 +139,7 @@ pub enum TrackSurface {
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(i32)]
 pub enum SessionState {
     #[default]
@@ -129,6 +153,7 @@ pub enum SessionState {
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(i32)]
 pub enum CarLeftRight {
     #[default]
@@ -142,6 +167,7 @@ pub enum CarLeftRight {
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(i32)]
 pub enum PitServiceStatus {
     #[default]
@@ -158,6 +184,7 @@ pub enum PitServiceStatus {
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(i32)]
 pub enum PaceMode {
     SingleFileStart = 0,
@@ -169,6 +196,7 @@ pub enum PaceMode {
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(i32)]
 pub enum TrackWetness {
     #[default]
@@ -181,3 +209,30 @@ pub enum TrackWetness {
     VeryWet,
     ExtremelyWet,
 }
+
+/// Implements [`VarValue`] for enums read from `Int` vars with the given unit
+macro_rules! impl_var_value {
+    ($($name:ident => $unit:literal,)+) => {
+        $(
+            impl VarValue for $name {
+                fn is_compatible(var: &VarHeader) -> bool {
+                    var.ty == VarType::Int && var.unit == $unit
+                }
+
+                fn from_bytes(bytes: &[u8]) -> Self {
+                    Self::from(align_cast::<i32, 4>(bytes))
+                }
+            }
+        )+
+    };
+}
+
+impl_var_value! {
+    TrackLocation => "irsdk_TrkLoc",
+    TrackSurface => "irsdk_TrkSurf",
+    SessionState => "irsdk_SessionState",
+    CarLeftRight => "irsdk_CarLeftRight",
+    PitServiceStatus => "irsdk_PitSvStatus",
+    PaceMode => "irsdk_PaceMode",
+    TrackWetness => "irsdk_TrackWetness",
+}
@@ -14,6 +14,7 @@ pub struct RawConversionError {
 /// General session info as well as byte offsets for variable buffers and the session
 /// string.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Header {
     pub tick_rate: u32,
 
@@ -32,6 +33,7 @@ pub struct Header {
 
 /// Session information specific to IBT files
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DiskSubHeader {
     /// Timestamp for the start of the session
     pub date: DateTime<Utc>,
@@ -50,6 +52,7 @@ pub struct DiskSubHeader {
 
 /// The status of a buffer of values
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VarBufInfo {
     /// In live telemetry, the tick this buffer's values represent. In a file, the number of ticks
     /// present in the data.
@@ -88,8 +91,16 @@ impl DiskSubHeader {
             date: DateTime::from_timestamp_secs(raw.start_date).ok_or(RawConversionError {
                 offset: std::mem::offset_of!(raw::DiskSubHeader, start_date),
             })?,
-            start_time: Duration::from_secs_f64(raw.start_time),
-            end_time: Duration::from_secs_f64(raw.end_time),
+            start_time: Duration::try_from_secs_f64(raw.start_time).map_err(|_| {
+                RawConversionError {
+                    offset: std::mem::offset_of!(raw::DiskSubHeader, start_time),
+                }
+            })?,
+            end_time: Duration::try_from_secs_f64(raw.end_time).map_err(|_| {
+                RawConversionError {
+                    offset: std::mem::offset_of!(raw::DiskSubHeader, end_time),
+                }
+            })?,
             lap_count: cast_field!(raw, lap_count, raw::DiskSubHeader)?,
             record_count: cast_field!(raw, record_count, raw::DiskSubHeader)?,
         })
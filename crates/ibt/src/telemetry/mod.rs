@@ -1,11 +1,17 @@
 //! Structured telemetry data
 
+mod array;
 pub mod bitfields;
 pub mod enums;
 mod headers;
 mod sample;
+mod typed;
 mod var;
 
+pub use array::{ArrayIter, ArrayView, TypedArrayVar};
 pub use headers::{DiskSubHeader, Header, RawConversionError, VarBufInfo};
+#[cfg(feature = "serde")]
+pub use sample::NamedSample;
 pub use sample::{Sample, Value};
-pub use var::{VarHeader, VarSet, VarType};
+pub use typed::{TypedReadError, TypedVar, VarValue};
+pub use var::{VarHeader, VarHeaderError, VarSet, VarType};
@@ -3,7 +3,10 @@ use std::borrow::Cow;
 
 use crate::{
     aligned::align_cast,
-    telemetry::{VarHeader, VarType, bitfields::Bitfield, enums::Enum},
+    telemetry::{
+        ArrayView, TypedArrayVar, TypedReadError, TypedVar, VarHeader, VarType, VarValue,
+        bitfields::Bitfield, enums::Enum,
+    },
 };
 
 /// A set of telemetry values at a specific point in time
@@ -21,40 +24,116 @@ impl<'data> Sample<'data> {
         Self(Cow::Owned(data.to_vec()))
     }
 
+    /// The raw bytes of the sample
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Copy the sample's data if it is borrowed, detaching it from its source
+    pub fn into_owned(self) -> Sample<'static> {
+        Sample(Cow::Owned(self.0.into_owned()))
+    }
+
     /// Extract a value from the sample
     pub fn read_var(&self, var: &VarHeader) -> Value {
-        let size = var.ty.size() * var.count;
-        let slice = &self.0[var.offset..var.offset + size];
-
-        if let Some(e) = Enum::parse(slice, var.unit.as_str()) {
-            return Value::Enum(e);
-        }
+        let slice = &self.0[var.offset..var.offset + var.size()];
+        let unit = var.unit.as_str();
 
         if var.count > 1 {
+            let values = slice.chunks_exact(var.ty.size());
             match var.ty {
+                VarType::Char => Value::CharArray(slice.iter().map(|c| *c as char).collect()),
                 VarType::Bool => Value::BoolArray(slice.iter().map(|b| *b != 0).collect()),
-                VarType::Int => Value::IntArray(pod_collect_to_vec(slice)),
+                VarType::Int => match values.map(|v| Enum::parse(v, unit)).collect() {
+                    Some(enums) => Value::EnumArray(enums),
+                    None => Value::IntArray(pod_collect_to_vec(slice)),
+                },
+                VarType::Bitfield => Value::BitfieldArray(
+                    values
+                        .map(|v| Bitfield::parse_unit(align_cast(v), unit))
+                        .collect(),
+                ),
                 VarType::Float => Value::FloatArray(pod_collect_to_vec(slice)),
-                _ => panic!("unsupported array type"),
+                VarType::Double => Value::DoubleArray(pod_collect_to_vec(slice)),
             }
         } else {
             match var.ty {
                 VarType::Char => Value::Char(slice[0] as char),
                 VarType::Bool => Value::Bool(slice[0] != 0),
-                VarType::Int => Value::Int(align_cast(slice)),
-                VarType::Bitfield => {
-                    let b = Bitfield::parse_unit(align_cast(slice), var.unit.as_str());
-                    Value::Bitfield(b)
-                }
+                VarType::Int => Enum::parse(slice, unit)
+                    .map_or_else(|| Value::Int(align_cast(slice)), Value::Enum),
+                VarType::Bitfield => Value::Bitfield(Bitfield::parse_unit(align_cast(slice), unit)),
                 VarType::Float => Value::Float(align_cast(slice)),
                 VarType::Double => Value::Double(align_cast(slice)),
             }
         }
     }
+
+    /// Read a single value of a var as `T`, without going through [`Value`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the var's type doesn't match `T` or the var is an array.
+    pub fn get<T: VarValue>(&self, var: &VarHeader) -> Result<T, TypedReadError> {
+        Ok(self.read(TypedVar::new(var)?))
+    }
+
+    /// Borrow all values of a var as `T`, without going through [`Value`] or allocating
+    ///
+    /// Works for both array and scalar vars.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the var's type doesn't match `T`.
+    pub fn get_array<T: VarValue>(
+        &self,
+        var: &VarHeader,
+    ) -> Result<ArrayView<'_, T>, TypedReadError> {
+        Ok(self.read_array(TypedArrayVar::new(var)?))
+    }
+
+    /// Read a var whose type was checked when the [`TypedVar`] was created
+    pub fn read<T: VarValue>(&self, var: TypedVar<T>) -> T {
+        T::from_bytes(&self.0[var.offset..var.offset + var.size])
+    }
+
+    /// Borrow an array var whose type was checked when the [`TypedArrayVar`] was created
+    pub fn read_array<T: VarValue>(&self, var: TypedArrayVar<T>) -> ArrayView<'_, T> {
+        let bytes = &self.0[var.offset..var.offset + var.size * var.count];
+        ArrayView::new(bytes, var.size)
+    }
+
+    /// Pair the sample with its vars, to serialize it as a map of var names to [`Value`]s
+    #[cfg(feature = "serde")]
+    pub fn with_vars<'a>(&'a self, vars: &'a crate::telemetry::VarSet) -> NamedSample<'a> {
+        NamedSample { sample: self, vars }
+    }
+}
+
+/// A [`Sample`] that serializes as a map of var names to [`Value`]s
+///
+/// Obtained from [`Sample::with_vars`].
+#[cfg(feature = "serde")]
+#[derive(Clone, Copy, Debug)]
+pub struct NamedSample<'a> {
+    sample: &'a Sample<'a>,
+    vars: &'a crate::telemetry::VarSet,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for NamedSample<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            self.vars
+                .all_vars()
+                .map(|var| (&var.name, self.sample.read_var(var))),
+        )
+    }
 }
 
 /// The value of a variable in a [`Sample`]
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value {
     Char(char),
     Bool(bool),
@@ -65,7 +144,135 @@ pub enum Value {
 
     Enum(Enum),
 
+    CharArray(Vec<char>),
     BoolArray(Vec<bool>),
     IntArray(Vec<i32>),
+    BitfieldArray(Vec<Bitfield>),
     FloatArray(Vec<f32>),
+    DoubleArray(Vec<f64>),
+
+    EnumArray(Vec<Enum>),
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_err, assert_matches, assert_ok};
+
+    use crate::{
+        raw,
+        telemetry::{
+            Sample, Value, VarHeader,
+            bitfields::{Bitfield, Flags},
+            enums::{Enum, TrackLocation},
+        },
+    };
+
+    fn array_var(ty: i32, count: i32, unit: &[u8]) -> VarHeader {
+        let raw = raw::VarHeader::new(ty, 0, count, 0, b"CarIdxVar", b"", unit);
+        assert_ok!(VarHeader::from_raw(&raw))
+    }
+
+    fn bytes(values: &[i32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn reads_enum_arrays() {
+        let data = bytes(&[3, 0, -1]);
+        let sample = Sample::new(&data);
+        let var = array_var(2, 3, b"irsdk_TrkLoc");
+
+        let value = sample.read_var(&var);
+        let Value::EnumArray(enums) = value else {
+            panic!("unexpected value {value:?}");
+        };
+        assert_matches!(
+            enums.as_slice(),
+            [
+                Enum::TrackLocation(TrackLocation::OnTrack),
+                Enum::TrackLocation(TrackLocation::OffTrack),
+                Enum::TrackLocation(TrackLocation::NotInWorld),
+            ]
+        );
+
+        let typed = assert_ok!(sample.get_array::<TrackLocation>(&var));
+        assert_eq!(
+            typed.to_vec(),
+            [
+                TrackLocation::OnTrack,
+                TrackLocation::OffTrack,
+                TrackLocation::NotInWorld
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_bitfield_arrays() {
+        let data = bytes(&[1 << 2, 1 << 5]);
+        let sample = Sample::new(&data);
+        let var = array_var(3, 2, b"irsdk_Flags");
+
+        let value = sample.read_var(&var);
+        let Value::BitfieldArray(flags) = value else {
+            panic!("unexpected value {value:?}");
+        };
+        assert_matches!(
+            flags.as_slice(),
+            [Bitfield::Flags(a), Bitfield::Flags(b)] if a.green() && b.blue()
+        );
+
+        let typed = assert_ok!(sample.get_array::<Flags>(&var));
+        assert!(typed.iter().all(|f| !f.checkered()));
+        assert_err!(sample.get_array::<Flags>(&array_var(3, 2, b"irsdk_PaceFlags")));
+    }
+
+    #[test]
+    fn reads_double_and_char_arrays() {
+        let data: Vec<u8> = [1.5_f64, 2.5]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        assert_matches!(
+            Sample::new(&data).read_var(&array_var(5, 2, b"")),
+            Value::DoubleArray(v) if v == [1.5, 2.5]
+        );
+        assert_matches!(
+            Sample::new(b"ab").read_var(&array_var(0, 2, b"")),
+            Value::CharArray(v) if v == ['a', 'b']
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_named_values() {
+        use crate::{IbtFile, test_utils::test_ibt};
+
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt(&[1.5])));
+        let sample = file.sample(0);
+        let json = assert_ok!(serde_json::to_string(&sample.with_vars(&file.vars)));
+        assert_eq!(
+            json,
+            r#"{"SessionTime":{"Double":1.5},"Speed":{"Float":3.0},"Lap":{"Int":1}}"#
+        );
+
+        let data = bytes(&[1 << 2 | 1 << 5]);
+        let value = Sample::new(&data).read_var(&array_var(3, 1, b"irsdk_Flags"));
+        let json = assert_ok!(serde_json::to_string(&value));
+        assert_eq!(json, r#"{"Bitfield":{"Flags":["green","blue"]}}"#);
+
+        let flags: Flags = assert_ok!(serde_json::from_str(r#"["green","blue"]"#));
+        assert!(flags.green() && flags.blue() && !flags.checkered());
+        assert_err!(serde_json::from_str::<Flags>(r#"["purple"]"#));
+
+        let flags = Flags::from_bits(1 << 2 | 1 << 22);
+        assert!(flags.flag_names().eq(["green", "bit22"]));
+        let json = assert_ok!(serde_json::to_string(&flags));
+        assert_eq!(json, r#"["green","bit22"]"#);
+        let flags: Flags = assert_ok!(serde_json::from_str(&json));
+        assert_eq!(flags.bits(), 1 << 2 | 1 << 22);
+        assert_eq!(
+            assert_ok!(serde_json::to_string(&TrackLocation::OnTrack)),
+            r#""OnTrack""#
+        );
+    }
 }
@@ -0,0 +1,153 @@
+use std::marker::PhantomData;
+
+use crate::{
+    aligned::align_cast,
+    telemetry::{VarHeader, VarType},
+};
+
+/// A var could not be read as the requested Rust type
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum TypedReadError {
+    /// No var with the given name exists
+    #[error("unknown var `{0}`")]
+    UnknownVar(String),
+
+    /// The var's type can't be read as the requested Rust type
+    #[error("var `{name}` has type `{ty:?}` and can't be read as `{requested}`")]
+    TypeMismatch {
+        name: String,
+        ty: VarType,
+        requested: &'static str,
+    },
+
+    /// A single value was requested from an array var
+    #[error("var `{name}` is an array of `{count}` values")]
+    NotScalar { name: String, count: usize },
+}
+
+/// A Rust type that values of a var can be read as
+///
+/// Implemented for `bool` ([`VarType::Bool`]), `char` ([`VarType::Char`]), `i32`
+/// ([`VarType::Int`]), `u32` ([`VarType::Bitfield`]), `f32` ([`VarType::Float`]) and `f64`
+/// ([`VarType::Double`]), as well as the types in [`enums`][crate::telemetry::enums] and
+/// [`bitfields`][crate::telemetry::bitfields] for vars with the matching unit.
+pub trait VarValue: Copy {
+    /// Whether values of `var` can be read as this type
+    fn is_compatible(var: &VarHeader) -> bool;
+
+    /// Decode a single value from exactly `var.ty.size()` bytes, which need not be aligned
+    fn from_bytes(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_var_value {
+    ($($t:ty => $var_type:ident, |$bytes:ident| $from_bytes:expr;)+) => {
+        $(
+            impl VarValue for $t {
+                fn is_compatible(var: &VarHeader) -> bool {
+                    var.ty == VarType::$var_type
+                }
+
+                fn from_bytes($bytes: &[u8]) -> Self {
+                    $from_bytes
+                }
+            }
+        )+
+    };
+}
+
+impl_var_value! {
+    bool => Bool, |bytes| bytes[0] != 0;
+    char => Char, |bytes| bytes[0] as char;
+    i32 => Int, |bytes| align_cast(bytes);
+    u32 => Bitfield, |bytes| align_cast(bytes);
+    f32 => Float, |bytes| align_cast(bytes);
+    f64 => Double, |bytes| align_cast(bytes);
+}
+
+/// A handle to a scalar var whose type has already been checked
+///
+/// Reading a `TypedVar` from a [`Sample`][crate::telemetry::Sample] is a plain load from a fixed
+/// offset, making it suitable for hot loops. Obtained from
+/// [`VarSet::typed`][crate::telemetry::VarSet::typed] or [`TypedVar::new`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TypedVar<T> {
+    pub(crate) offset: usize,
+    pub(crate) size: usize,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T: VarValue> TypedVar<T> {
+    /// Check that `var` is a single value that can be read as `T`
+    pub fn new(var: &VarHeader) -> Result<Self, TypedReadError> {
+        check_type::<T>(var)?;
+        if var.count != 1 {
+            return Err(TypedReadError::NotScalar {
+                name: var.name.clone(),
+                count: var.count,
+            });
+        }
+
+        Ok(Self {
+            offset: var.offset,
+            size: var.ty.size(),
+            _type: PhantomData,
+        })
+    }
+}
+
+pub(crate) fn check_type<T: VarValue>(var: &VarHeader) -> Result<(), TypedReadError> {
+    if T::is_compatible(var) {
+        Ok(())
+    } else {
+        Err(TypedReadError::TypeMismatch {
+            name: var.name.clone(),
+            ty: var.ty,
+            requested: std::any::type_name::<T>(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_matches, assert_ok, assert_ok_eq};
+
+    use crate::{
+        IbtFile,
+        telemetry::{TypedReadError, TypedVar, VarType},
+        test_utils::test_ibt,
+    };
+
+    #[test]
+    fn reads_typed_values() {
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt(&[1.0, 1.5])));
+        let sample = file.sample(1);
+
+        let time = file.vars.var("SessionTime").unwrap();
+        let speed = file.vars.var("Speed").unwrap();
+        assert_ok_eq!(sample.get::<f64>(time), 1.5);
+        assert_ok_eq!(sample.get::<f32>(speed), 3.0);
+        assert_eq!(assert_ok!(sample.get_array::<f32>(speed)).to_vec(), [3.0]);
+
+        let lap: TypedVar<i32> = assert_ok!(file.vars.typed("Lap"));
+        assert_eq!(sample.read(lap), 1);
+    }
+
+    #[test]
+    fn rejects_mismatched_types() {
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt(&[1.0])));
+        let speed = file.vars.var("Speed").unwrap();
+
+        assert_matches!(
+            file.sample(0).get::<f64>(speed),
+            Err(TypedReadError::TypeMismatch {
+                ty: VarType::Float,
+                requested: "f64",
+                ..
+            })
+        );
+        assert_matches!(
+            file.vars.typed::<i32>("RPM"),
+            Err(TypedReadError::UnknownVar(_))
+        );
+    }
+}
@@ -1,9 +1,44 @@
-use std::ffi::c_char;
+use std::ffi::{c_char, c_int};
 
 use indexmap::IndexMap;
 use num_enum::TryFromPrimitive;
 
-use crate::raw;
+use crate::{
+    raw,
+    telemetry::{TypedArrayVar, TypedReadError, TypedVar, VarValue},
+};
+
+/// A raw var header could not be decoded
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum VarHeaderError {
+    /// The var's type is not one of the known [`VarType`]s
+    #[error("invalid var type: `{0}`")]
+    InvalidType(c_int),
+
+    /// The var's offset within a sample is negative
+    #[error("invalid var offset: `{0}`")]
+    InvalidOffset(c_int),
+
+    /// The var's count is not positive
+    #[error("invalid var count: `{0}`")]
+    InvalidCount(c_int),
+
+    /// A string field is not NUL-terminated
+    #[error("`{0}` is not NUL-terminated")]
+    UnterminatedString(&'static str),
+
+    /// A string field is too long to be encoded
+    #[error("`{0}` is too long")]
+    StringTooLong(&'static str),
+
+    /// A string field contains characters outside of ISO-8859-1
+    #[error("`{0}` contains characters outside of ISO-8859-1")]
+    InvalidString(&'static str),
+
+    /// A numeric field is too large to be encoded
+    #[error("`{0}` is too large")]
+    FieldTooLarge(&'static str),
+}
 
 /// Map of variable names to their headers
 #[derive(Clone, Debug)]
@@ -26,9 +61,35 @@ impl VarSet {
     pub fn all_vars(&self) -> impl Iterator<Item = &VarHeader> {
         self.0.values()
     }
+
+    /// Get a handle for reading a scalar var as `T`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no var with the given name, its type doesn't match `T`, or
+    /// it is an array.
+    pub fn typed<T: VarValue>(&self, name: &str) -> Result<TypedVar<T>, TypedReadError> {
+        let var = self
+            .var(name)
+            .ok_or_else(|| TypedReadError::UnknownVar(name.to_string()))?;
+        TypedVar::new(var)
+    }
+
+    /// Get a handle for reading an array var as `T`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no var with the given name or its type doesn't match `T`.
+    pub fn typed_array<T: VarValue>(&self, name: &str) -> Result<TypedArrayVar<T>, TypedReadError> {
+        let var = self
+            .var(name)
+            .ok_or_else(|| TypedReadError::UnknownVar(name.to_string()))?;
+        TypedArrayVar::new(var)
+    }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive, serde::Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(i32)]
 pub enum VarType {
     /// 1-byte character
@@ -59,7 +120,8 @@ impl VarType {
 /// Describes one of the variables available in a telemetry sample
 ///
 /// Obtained from a `VarSet` constructed from a telemetry file or live telemetry.
-#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VarHeader {
     /// The type of the variable
     pub ty: VarType,
@@ -84,41 +146,102 @@ pub struct VarHeader {
 }
 
 impl VarHeader {
-    pub fn from_raw(raw: &raw::VarHeader) -> Self {
+    pub fn from_raw(raw: &raw::VarHeader) -> Result<Self, VarHeaderError> {
         let ty = raw
             .ty
             .try_into()
-            .unwrap_or_else(|_| panic!("invalid var type: `{}`", raw.ty));
+            .map_err(|_| VarHeaderError::InvalidType(raw.ty))?;
 
-        Self {
+        Ok(Self {
             ty,
-            offset: raw.offset.try_into().expect("`offset` should be positive"),
-            count: raw.count.try_into().expect("`count` should be positive"),
+            offset: raw
+                .offset
+                .try_into()
+                .map_err(|_| VarHeaderError::InvalidOffset(raw.offset))?,
+            count: usize::try_from(raw.count)
+                .ok()
+                .filter(|count| *count > 0)
+                .ok_or(VarHeaderError::InvalidCount(raw.count))?,
             count_as_time: raw.count_as_time == 0,
-            name: string_from_c_chars(&raw.name),
-            description: string_from_c_chars(&raw.desc),
-            unit: string_from_c_chars(&raw.unit),
-        }
+            name: string_from_c_chars(&raw.name, "name")?,
+            description: string_from_c_chars(&raw.desc, "desc")?,
+            unit: string_from_c_chars(&raw.unit, "unit")?,
+        })
+    }
+
+    /// Encode the var header as it is laid out in a telemetry file
+    pub fn to_raw(&self) -> Result<raw::VarHeader, VarHeaderError> {
+        Ok(raw::VarHeader {
+            ty: self.ty as c_int,
+            offset: self
+                .offset
+                .try_into()
+                .map_err(|_| VarHeaderError::FieldTooLarge("offset"))?,
+            count: self
+                .count
+                .try_into()
+                .map_err(|_| VarHeaderError::FieldTooLarge("count"))?,
+            count_as_time: c_char::from(!self.count_as_time),
+            _pad: [0; 3],
+            name: c_chars_from_str(&self.name, "name")?,
+            desc: c_chars_from_str(&self.description, "desc")?,
+            unit: c_chars_from_str(&self.unit, "unit")?,
+        })
+    }
+
+    /// Number of values of this var in each sample, 1 for non-array vars
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Size in bytes of all of this var's values in a sample
+    pub(crate) fn size(&self) -> usize {
+        self.ty.size() * self.count
     }
 }
 
-fn string_from_c_chars(buf: &[c_char]) -> String {
-    assert!(buf.contains(&0));
+fn string_from_c_chars(buf: &[c_char], field: &'static str) -> Result<String, VarHeaderError> {
+    if !buf.contains(&0) {
+        return Err(VarHeaderError::UnterminatedString(field));
+    }
     // Strings in iRacing are all ISO-8859-1, which is effectively a subset of UTF-8. Therefore, it
     // is safe to interpret a string buffer as unsiged bytes and cast them to UTF-8 codepoints.
     //
     // https://forums.iracing.com/discussion/comment/703469/#Comment_703469
-    buf.iter()
+    Ok(buf
+        .iter()
         .map(|c| *c as u8 as char)
         .take_while(|c| *c != '\0')
-        .collect()
+        .collect())
+}
+
+/// Encode a string as a NUL-terminated ISO-8859-1 buffer, see [`string_from_c_chars`]
+fn c_chars_from_str<const N: usize>(
+    s: &str,
+    field: &'static str,
+) -> Result<[c_char; N], VarHeaderError> {
+    // leave room for the NUL terminator
+    if s.chars().count() >= N {
+        return Err(VarHeaderError::StringTooLong(field));
+    }
+
+    let mut buf = [0; N];
+    for (c_char, c) in buf.iter_mut().zip(s.chars()) {
+        let byte = u8::try_from(c).map_err(|_| VarHeaderError::InvalidString(field))?;
+        *c_char = byte as c_char;
+    }
+    Ok(buf)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::ffi::c_char;
+
+    use claims::{assert_matches, assert_ok_eq};
+
     use crate::{
         raw,
-        telemetry::{VarHeader, VarType},
+        telemetry::{VarHeader, VarHeaderError, VarType},
     };
 
     #[test]
@@ -134,7 +257,7 @@ mod tests {
         );
         let var_header = VarHeader::from_raw(&raw);
 
-        assert_eq!(
+        assert_ok_eq!(
             var_header,
             VarHeader {
                 ty: VarType::Double,
@@ -149,12 +272,58 @@ mod tests {
     }
 
     #[test]
-    #[should_panic = "invalid var type: `99`"]
-    fn panics_with_invalid_var_type() {
+    fn encodes_var_header() {
+        let raw = raw::VarHeader::new(
+            5,
+            0,
+            1,
+            0,
+            b"SessionTime",
+            b"Seconds since session start",
+            b"s",
+        );
+        let var_header = VarHeader::from_raw(&raw).unwrap();
+
+        assert_ok_eq!(var_header.to_raw(), raw);
+    }
+
+    #[test]
+    fn rejects_invalid_var_type() {
         let raw = raw::VarHeader::new(
             99, // invalid
             0, 1, 0, b"", b"", b"",
         );
-        VarHeader::from_raw(&raw);
+        assert_matches!(
+            VarHeader::from_raw(&raw),
+            Err(VarHeaderError::InvalidType(99))
+        );
+    }
+
+    #[test]
+    fn rejects_negative_offset() {
+        let raw = raw::VarHeader::new(5, -8, 1, 0, b"", b"", b"");
+        assert_matches!(
+            VarHeader::from_raw(&raw),
+            Err(VarHeaderError::InvalidOffset(-8))
+        );
+    }
+
+    #[test]
+    fn rejects_empty_count() {
+        let raw = raw::VarHeader::new(5, 0, 0, 0, b"", b"", b"");
+        assert_matches!(
+            VarHeader::from_raw(&raw),
+            Err(VarHeaderError::InvalidCount(0))
+        );
+    }
+
+    #[test]
+    fn rejects_unterminated_string() {
+        let mut raw = raw::VarHeader::new(5, 0, 1, 0, b"", b"", b"");
+        raw.name = [b'a' as c_char; 32];
+        assert_matches!(
+            VarHeader::from_raw(&raw),
+            Err(VarHeaderError::UnterminatedString("name"))
+        );
     }
 }
@@ -19,3 +19,189 @@ pub fn test_string<const N: usize>(val: &[u8]) -> [i8; N] {
 
     arr.map(|b| b as i8)
 }
+
+/// Builds the bytes of a minimal `.ibt` file with `SessionTime` (double), `Speed` (float) and
+/// `Lap` (int) vars and one sample per given session time. `Speed` is twice the session time and
+/// `Lap` is always `1`.
+pub fn test_ibt(session_times: &[f64]) -> Vec<u8> {
+    let samples: Vec<_> = session_times.iter().map(|time| (*time, 1)).collect();
+    test_ibt_with_laps(&samples)
+}
+
+/// Like [`test_ibt`], but with a `Lap` value for each sample
+pub fn test_ibt_with_laps(samples: &[(f64, i32)]) -> Vec<u8> {
+    test_ibt_with_vars(&[
+        (
+            "SessionTime",
+            "s",
+            TestValues::Double(samples.iter().map(|s| s.0).collect()),
+        ),
+        (
+            "Speed",
+            "m/s",
+            TestValues::Float(samples.iter().map(|s| s.0 as f32 * 2.0).collect()),
+        ),
+        (
+            "Lap",
+            "",
+            TestValues::Int(samples.iter().map(|s| s.1).collect()),
+        ),
+    ])
+}
+
+/// Builds a file of a car doing a lap in 10 s, then a lap where the second half takes twice as
+/// long, sampled every second. Distance is counted in twentieths of a lap, with `SessionTime`,
+/// `Lap`, `LapDistPct` and `Speed` vars.
+pub fn test_ibt_with_slow_lap() -> Vec<u8> {
+    let mut dist = vec![17];
+    for _ in 0..30 {
+        let d = dist.last().unwrap();
+        dist.push(d + if (50..60).contains(d) { 1 } else { 2 });
+    }
+    let speed = dist.windows(2).map(|d| (d[1] - d[0]) as f32).chain([2.0]);
+
+    test_ibt_with_vars(&[
+        (
+            "SessionTime",
+            "s",
+            TestValues::Double((0..dist.len()).map(|t| t as f64).collect()),
+        ),
+        (
+            "Lap",
+            "",
+            TestValues::Int(dist.iter().map(|d| d / 20).collect()),
+        ),
+        (
+            "LapDistPct",
+            "%",
+            TestValues::Float(dist.iter().map(|d| (d % 20) as f32 / 20.0).collect()),
+        ),
+        ("Speed", "m/s", TestValues::Float(speed.collect())),
+    ])
+}
+
+/// The values of a var in a file built by [`test_ibt_with_vars`], one per sample
+pub enum TestValues {
+    Bool(Vec<bool>),
+    Int(Vec<i32>),
+    Bitfield(Vec<u32>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+}
+
+impl TestValues {
+    fn len(&self) -> usize {
+        match self {
+            Self::Bool(v) => v.len(),
+            Self::Int(v) => v.len(),
+            Self::Bitfield(v) => v.len(),
+            Self::Float(v) => v.len(),
+            Self::Double(v) => v.len(),
+        }
+    }
+
+    /// `(type, size)` of the var
+    fn ty(&self) -> (i32, usize) {
+        match self {
+            Self::Bool(_) => (1, 1),
+            Self::Int(_) => (2, 4),
+            Self::Bitfield(_) => (3, 4),
+            Self::Float(_) => (4, 4),
+            Self::Double(_) => (5, 8),
+        }
+    }
+
+    fn write(&self, idx: usize, out: &mut Vec<u8>) {
+        match self {
+            Self::Bool(v) => out.push(v[idx].into()),
+            Self::Int(v) => out.extend(v[idx].to_le_bytes()),
+            Self::Bitfield(v) => out.extend(v[idx].to_le_bytes()),
+            Self::Float(v) => out.extend(v[idx].to_le_bytes()),
+            Self::Double(v) => out.extend(v[idx].to_le_bytes()),
+        }
+    }
+}
+
+/// Builds the bytes of a minimal `.ibt` file with the given `(name, unit, values)` vars, laid
+/// out in order in each sample. All vars must have the same number of values.
+///
+/// The disk sub-header's times and lap count are taken from the `SessionTime` and `Lap` vars, if
+/// there are any. The session string splits the lap into two sectors at half distance.
+pub fn test_ibt_with_vars(vars: &[(&str, &str, TestValues)]) -> Vec<u8> {
+    const SESSION: &[u8] =
+        b"---\nWeekendInfo:\n TrackName: test\n SessionID: 1\n SubSessionID: 2\nSplitTimeInfo:\n Sectors:\n - SectorNum: 0\n   SectorStartPct: 0.000000\n - SectorNum: 1\n   SectorStartPct: 0.500000\n...\n";
+
+    let sample_count = vars.first().map(|v| v.2.len()).unwrap_or_default();
+    assert!(vars.iter().all(|v| v.2.len() == sample_count));
+    let buf_len: usize = vars.iter().map(|v| v.2.ty().1).sum();
+
+    let var_header_offset = 144;
+    let session_info_offset = var_header_offset + 144 * vars.len() as i32;
+    let buf_offset = session_info_offset + SESSION.len() as i32;
+
+    let mut out = Vec::new();
+    // header
+    for field in [
+        2,
+        1,
+        60,
+        0,
+        SESSION.len() as i32,
+        session_info_offset,
+        vars.len() as i32,
+        var_header_offset,
+        1,
+        buf_len as i32,
+    ] {
+        out.extend(field.to_le_bytes());
+    }
+    out.extend([0; 8]);
+    out.extend((sample_count as i32).to_le_bytes());
+    out.extend(buf_offset.to_le_bytes());
+    out.extend([0; 8 + 16 * 3]);
+
+    // disk sub header
+    let values = |name| vars.iter().find(|v| v.0 == name).map(|v| &v.2);
+    let (start_time, end_time) = match values("SessionTime") {
+        Some(TestValues::Double(times)) => (
+            times.first().copied().unwrap_or_default(),
+            times.last().copied().unwrap_or_default(),
+        ),
+        _ => (0.0, 0.0),
+    };
+    let lap_count = match values("Lap") {
+        Some(TestValues::Int(laps)) if !laps.is_empty() => {
+            laps.iter().max().unwrap() - laps.iter().min().unwrap() + 1
+        }
+        _ => 1,
+    };
+    out.extend(1_764_642_265_i64.to_le_bytes());
+    out.extend(start_time.to_le_bytes());
+    out.extend(end_time.to_le_bytes());
+    out.extend(lap_count.to_le_bytes());
+    out.extend((sample_count as i32).to_le_bytes());
+
+    // var headers
+    let mut offset = 0;
+    for (name, unit, values) in vars {
+        let (ty, size) = values.ty();
+        out.extend(ty.to_le_bytes());
+        out.extend((offset as i32).to_le_bytes());
+        out.extend(1_i32.to_le_bytes());
+        out.extend([0; 4]);
+        out.extend(test_string::<32>(name.as_bytes()).map(|c| c as u8));
+        out.extend([0; 64]);
+        out.extend(test_string::<32>(unit.as_bytes()).map(|c| c as u8));
+        offset += size;
+    }
+
+    out.extend(SESSION);
+
+    for idx in 0..sample_count {
+        for (_, _, values) in vars {
+            values.write(idx, &mut out);
+        }
+    }
+
+    out
+}
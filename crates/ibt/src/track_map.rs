@@ -0,0 +1,436 @@
+//! Track maps from the `Lat`, `Lon` and `Alt` vars
+//!
+//! These vars are only written to disk, not to live telemetry. Positions are projected onto a
+//! plane around the middle of the track, with `x` pointing east and `y` pointing north in
+//! meters, which is accurate to well under a meter over the size of a track.
+
+use std::fmt::Write;
+
+use crate::{
+    IbtFile,
+    laps::Lap,
+    resample::{Axis, ResampleError},
+    runs::runs,
+    telemetry::TypedReadError,
+};
+
+/// Mean radius of the earth, in meters
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TrackMapError {
+    #[error(transparent)]
+    Resample(#[from] ResampleError),
+
+    #[error(transparent)]
+    Var(#[from] TypedReadError),
+
+    /// The lap has no samples with a position
+    #[error("lap has no positions")]
+    NoPositions,
+}
+
+/// A point on the track
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackPoint {
+    /// Lap distance of the point, from 0 to 1
+    pub pct: f64,
+    /// Latitude, in degrees
+    pub lat: f64,
+    /// Longitude, in degrees
+    pub lon: f64,
+    /// Altitude, in meters
+    pub alt: f64,
+    /// Meters east of the map's origin
+    pub x: f64,
+    /// Meters north of the map's origin
+    pub y: f64,
+}
+
+/// The outline of a track, see [`IbtFile::track_map`]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackMap {
+    /// Latitude and longitude that `x` and `y` are measured from, in degrees
+    pub origin: (f64, f64),
+    /// Points along the racing line of the lap, at fixed steps of `LapDistPct`
+    pub centerline: Vec<TrackPoint>,
+    /// Points along pit lane, or empty if the file never visits it
+    pub pit_lane: Vec<TrackPoint>,
+}
+
+impl TrackMap {
+    /// Project a latitude and longitude onto the map, returning `(x, y)` in meters
+    pub fn project(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let (lat0, lon0) = self.origin;
+        let x = (lon - lon0).to_radians() * lat0.to_radians().cos() * EARTH_RADIUS;
+        let y = (lat - lat0).to_radians() * EARTH_RADIUS;
+        (x, y)
+    }
+
+    fn point(&self, pct: f64, lat: f64, lon: f64, alt: f64) -> TrackPoint {
+        let (x, y) = self.project(lat, lon);
+        TrackPoint {
+            pct,
+            lat,
+            lon,
+            alt,
+            x,
+            y,
+        }
+    }
+
+    /// The point at a lap distance, interpolated along the centerline
+    ///
+    /// Use this to draw cars from their `CarIdxLapDistPct`.
+    pub fn point_at(&self, pct: f64) -> Option<TrackPoint> {
+        let pct = pct.rem_euclid(1.0);
+        let after = self.centerline.iter().position(|p| p.pct > pct);
+        let (a, b) = match after {
+            Some(0) | None => (self.centerline.last()?, self.centerline.first()?),
+            Some(idx) => (&self.centerline[idx - 1], &self.centerline[idx]),
+        };
+
+        // the segment from the last point to the first wraps around the line
+        let span = (b.pct - a.pct).rem_euclid(1.0);
+        let frac = if span > 0.0 {
+            (pct - a.pct).rem_euclid(1.0) / span
+        } else {
+            0.0
+        };
+        let lerp = |a: f64, b: f64| a + (b - a) * frac;
+        Some(TrackPoint {
+            pct,
+            lat: lerp(a.lat, b.lat),
+            lon: lerp(a.lon, b.lon),
+            alt: lerp(a.alt, b.alt),
+            x: lerp(a.x, b.x),
+            y: lerp(a.y, b.y),
+        })
+    }
+
+    /// Length of the closed centerline, in meters
+    pub fn length(&self) -> f64 {
+        let points = &self.centerline;
+        points
+            .iter()
+            .zip(points.iter().skip(1).chain(points.first()))
+            .map(|(a, b)| (b.x - a.x).hypot(b.y - a.y))
+            .sum()
+    }
+
+    /// The map as a GeoJSON `FeatureCollection`
+    ///
+    /// The centerline is a closed `LineString` and pit lane, if there is one, an open one. Each
+    /// feature has a `name` property of `centerline` or `pit_lane`. Points with coordinates that
+    /// aren't finite are left out, as JSON can't represent them.
+    pub fn to_geojson(&self) -> String {
+        let line = |name: &str, points: &[TrackPoint], closed: bool| {
+            let mut coords: Vec<String> = points
+                .iter()
+                .filter(|p| [p.lon, p.lat, p.alt].iter().all(|v| v.is_finite()))
+                .map(|p| format!("[{},{},{}]", p.lon, p.lat, p.alt))
+                .collect();
+            if let (true, Some(first)) = (closed, coords.first().cloned()) {
+                coords.push(first);
+            }
+            format!(
+                r#"{{"type":"Feature","properties":{{"name":"{name}"}},"geometry":{{"type":"LineString","coordinates":[{}]}}}}"#,
+                coords.join(",")
+            )
+        };
+
+        let mut features = vec![line("centerline", &self.centerline, true)];
+        if !self.pit_lane.is_empty() {
+            features.push(line("pit_lane", &self.pit_lane, false));
+        }
+        format!(
+            r#"{{"type":"FeatureCollection","features":[{}]}}"#,
+            features.join(",")
+        )
+    }
+
+    /// The map as an SVG document
+    ///
+    /// One user unit is one meter, with the `y` axis flipped so north is up: a point is drawn at
+    /// `(x, -y)`. The centerline and pit lane are `path`s with the ids `centerline` and
+    /// `pit_lane`, so they can be styled with CSS.
+    pub fn to_svg(&self) -> String {
+        const MARGIN: f64 = 20.0;
+
+        let all = || self.centerline.iter().chain(&self.pit_lane);
+        let fold = |init: f64, f: fn(f64, f64) -> f64, value: fn(&TrackPoint) -> f64| {
+            all().map(value).fold(init, f)
+        };
+        let (min_x, max_x) = (
+            fold(f64::INFINITY, f64::min, |p| p.x),
+            fold(f64::NEG_INFINITY, f64::max, |p| p.x),
+        );
+        let (min_y, max_y) = (
+            fold(f64::INFINITY, f64::min, |p| -p.y),
+            fold(f64::NEG_INFINITY, f64::max, |p| -p.y),
+        );
+        let (min_x, min_y, width, height) = if min_x.is_finite() {
+            (min_x, min_y, max_x - min_x, max_y - min_y)
+        } else {
+            (0.0, 0.0, 0.0, 0.0)
+        };
+
+        let path = |points: &[TrackPoint], closed: bool| {
+            let mut d = String::new();
+            for (idx, p) in points.iter().enumerate() {
+                let cmd = if idx == 0 { 'M' } else { 'L' };
+                let _ = write!(d, "{cmd}{:.2} {:.2} ", p.x, -p.y);
+            }
+            if closed {
+                d.push('Z');
+            }
+            d.trim_end().to_string()
+        };
+
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{:.2} {:.2} {:.2} {:.2}">"#,
+            min_x - MARGIN,
+            min_y - MARGIN,
+            width + 2.0 * MARGIN,
+            height + 2.0 * MARGIN,
+        );
+        let _ = write!(
+            svg,
+            r#"<path id="centerline" d="{}" fill="none" stroke="black" stroke-width="8"/>"#,
+            path(&self.centerline, true)
+        );
+        if !self.pit_lane.is_empty() {
+            let _ = write!(
+                svg,
+                r#"<path id="pit_lane" d="{}" fill="none" stroke="grey" stroke-width="4"/>"#,
+                path(&self.pit_lane, false)
+            );
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+}
+
+impl IbtFile {
+    /// Build a track map from a lap of this file, with centerline points every `step` of
+    /// `LapDistPct`
+    ///
+    /// Pick a clean, complete lap: the centerline follows the car. Pit lane is taken from the
+    /// longest run of samples anywhere in the file with `OnPitRoad` set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file lacks the `Lat`, `Lon`, `Alt` or `LapDistPct` vars, the step
+    /// is not positive, or the lap has no positions.
+    pub fn track_map(&self, lap: &Lap, step: f64) -> Result<TrackMap, TrackMapError> {
+        let lat: Vec<f64> = self.column("Lat")?;
+        let lon: Vec<f64> = self.column("Lon")?;
+        // drop samples without a position first, so no points are interpolated towards them
+        let resampled = self.resample_where(
+            &["Lat", "Lon", "Alt"],
+            lap.sample_range.clone(),
+            Axis::LapDistPct { step },
+            |idx| has_position(lat[idx], lon[idx]),
+        )?;
+        let columns = &resampled.columns.columns;
+        let (lat, lon, alt) = (
+            columns[0].to_f64()?,
+            columns[1].to_f64()?,
+            columns[2].to_f64()?,
+        );
+        if lat.is_empty() {
+            return Err(TrackMapError::NoPositions);
+        }
+
+        let mut map = TrackMap {
+            origin: (
+                lat.iter().sum::<f64>() / lat.len() as f64,
+                lon.iter().sum::<f64>() / lon.len() as f64,
+            ),
+            ..TrackMap::default()
+        };
+        map.centerline = resampled
+            .axis
+            .iter()
+            .zip(lat.iter().zip(&lon).zip(&alt))
+            .map(|(pct, ((lat, lon), alt))| map.point(*pct, *lat, *lon, *alt))
+            .collect();
+        map.pit_lane = self.pit_lane(&map)?;
+        Ok(map)
+    }
+
+    /// Points of the longest run of samples on pit road
+    fn pit_lane(&self, map: &TrackMap) -> Result<Vec<TrackPoint>, TypedReadError> {
+        let Some(on_pit_road) = self.optional_column::<bool>("OnPitRoad")? else {
+            return Ok(Vec::new());
+        };
+        // the first of the longest runs
+        let Some(longest) = runs(&on_pit_road)
+            .into_iter()
+            .rev()
+            .max_by_key(ExactSizeIterator::len)
+        else {
+            return Ok(Vec::new());
+        };
+
+        let lat: Vec<f64> = self.column("Lat")?;
+        let lon: Vec<f64> = self.column("Lon")?;
+        let alt: Vec<f32> = self.column("Alt")?;
+        let pct: Vec<f32> = self.column("LapDistPct")?;
+        Ok(longest
+            .filter(|idx| has_position(lat[*idx], lon[*idx]))
+            .map(|idx| map.point(f64::from(pct[idx]), lat[idx], lon[idx], f64::from(alt[idx])))
+            .collect())
+    }
+}
+
+/// Samples without a position have a latitude and longitude of zero
+fn has_position(lat: f64, lon: f64) -> bool {
+    !(lat == 0.0 && lon == 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::TAU;
+
+    use claims::{assert_ok, assert_some};
+
+    use crate::{
+        IbtFile,
+        test_utils::{TestValues, test_ibt_with_vars},
+        track_map::{TrackMap, TrackPoint},
+    };
+
+    const RADIUS: f64 = 100.0;
+
+    /// A car going around a circle with a radius of 100 m, 40 samples per lap, with a trip down
+    /// a pit lane inside the circle at the end. The samples in `no_position` have no position.
+    fn test_file(no_position: &[usize]) -> IbtFile {
+        let (lat0, lon0) = (50.0_f64, 6.0_f64);
+        let to_lat = |y: f64| lat0 + (y / 6_371_000.0).to_degrees();
+        let to_lon = |x: f64| lon0 + (x / (6_371_000.0 * lat0.to_radians().cos())).to_degrees();
+        let position = |i: usize, value: f64| {
+            if no_position.contains(&i) { 0.0 } else { value }
+        };
+
+        let count = 100;
+        let dist: Vec<usize> = (0..count).map(|i| i + 35).collect();
+        let pct: Vec<f64> = dist.iter().map(|d| (d % 40) as f64 / 40.0).collect();
+        let on_pit_road: Vec<bool> = (0..count).map(|i| i >= 92).collect();
+        let radius = |i: usize| {
+            if on_pit_road[i] {
+                RADIUS - 20.0
+            } else {
+                RADIUS
+            }
+        };
+
+        IbtFile::from_bytes(&test_ibt_with_vars(&[
+            (
+                "SessionTime",
+                "s",
+                TestValues::Double((0..count).map(|t| t as f64).collect()),
+            ),
+            (
+                "Lap",
+                "",
+                TestValues::Int(dist.iter().map(|d| (d / 40) as i32).collect()),
+            ),
+            (
+                "LapDistPct",
+                "%",
+                TestValues::Float(pct.iter().map(|p| *p as f32).collect()),
+            ),
+            (
+                "Lat",
+                "deg",
+                TestValues::Double(
+                    (0..count)
+                        .map(|i| position(i, to_lat(radius(i) * (pct[i] * TAU).sin())))
+                        .collect(),
+                ),
+            ),
+            (
+                "Lon",
+                "deg",
+                TestValues::Double(
+                    (0..count)
+                        .map(|i| position(i, to_lon(radius(i) * (pct[i] * TAU).cos())))
+                        .collect(),
+                ),
+            ),
+            ("Alt", "m", TestValues::Float(vec![400.0; count])),
+            ("OnPitRoad", "", TestValues::Bool(on_pit_road)),
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn maps_track() {
+        let file = test_file(&[]);
+        let laps = assert_ok!(file.laps());
+        assert!(laps[1].is_complete());
+        let map = assert_ok!(file.track_map(&laps[1], 0.01));
+
+        assert!(map.centerline.len() > 90);
+        // the origin is the middle of the points, not quite the center of the circle
+        let (cx, cy) = map.project(50.0, 6.0);
+        for point in &map.centerline {
+            assert!(((point.x - cx).hypot(point.y - cy) - RADIUS).abs() < 1.0);
+        }
+        // the polyline cuts the corners of the circle a little
+        assert!((map.length() - TAU * RADIUS).abs() < 2.0);
+        assert_eq!(map.pit_lane.len(), 8);
+
+        let point = assert_some!(map.point_at(0.25));
+        assert!((point.x - cx).abs() < 1.0 && (point.y - cy - RADIUS).abs() < 1.0);
+        let point = assert_some!(map.point_at(0.999));
+        assert!((point.x - cx - RADIUS).abs() < 1.0);
+    }
+
+    #[test]
+    fn exports_geojson_and_svg() {
+        let file = test_file(&[]);
+        let map = assert_ok!(file.track_map(&assert_ok!(file.laps())[1], 0.1));
+
+        let geojson = map.to_geojson();
+        assert!(geojson.starts_with(r#"{"type":"FeatureCollection","features":[{"type":"Feature","properties":{"name":"centerline"}"#));
+        assert!(geojson.contains(r#""name":"pit_lane""#));
+
+        let svg = map.to_svg();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(r#"<path id="centerline" d="M"#));
+        assert!(svg.ends_with("</svg>"));
+    }
+
+    #[test]
+    fn skips_samples_without_position() {
+        let file = test_file(&[20, 21]);
+        let map = assert_ok!(file.track_map(&assert_ok!(file.laps())[1], 0.01));
+
+        let (cx, cy) = map.project(50.0, 6.0);
+        for point in &map.centerline {
+            // the gap is bridged by a chord of the circle, rather than a line to (0, 0)
+            assert!(((point.x - cx).hypot(point.y - cy) - RADIUS).abs() < 5.0);
+        }
+    }
+
+    #[test]
+    fn leaves_non_finite_points_out_of_geojson() {
+        let point = |lat: f64| TrackPoint {
+            lat,
+            lon: 6.0,
+            ..TrackPoint::default()
+        };
+        let map = TrackMap {
+            centerline: vec![point(50.0), point(f64::NAN)],
+            ..TrackMap::default()
+        };
+
+        let geojson = map.to_geojson();
+        assert!(!geojson.contains("NaN"));
+        assert!(geojson.contains("[[6,50,0],[6,50,0]]"));
+    }
+}
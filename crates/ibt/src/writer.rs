@@ -0,0 +1,334 @@
+use std::{
+    ffi::c_int,
+    io::{Seek, SeekFrom, Write},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    file::FileHeaders,
+    raw,
+    telemetry::{DiskSubHeader, Header, Sample, Value, VarHeader, VarHeaderError, VarSet},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum IbtWriterError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    /// A var header could not be encoded
+    #[error("var `{name}` could not be encoded")]
+    InvalidVarHeader {
+        name: String,
+        #[source]
+        source: VarHeaderError,
+    },
+
+    /// A sample buffer was not [`Header::buf_len`] bytes long
+    #[error("sample is `{len}` bytes long, expected `{buf_len}`")]
+    InvalidSampleLength { len: usize, buf_len: usize },
+
+    /// A size or offset is too large to be stored in an IBT file
+    #[error("`{0}` is too large to be stored in an IBT file")]
+    TooLarge(&'static str),
+}
+
+/// Writes a `.ibt` file
+///
+/// The var headers and session string are written when the writer is created, followed by each
+/// sample as it is written. The header and disk sub-header are filled in once the writer is
+/// finished, which is why the output must be seekable.
+///
+/// # Example
+/// ```ignore
+/// # use ibt::{IbtFile, IbtWriter};
+///
+/// let file = IbtFile::from_file("example-telemetry-file.ibt").unwrap();
+/// let out = std::fs::File::create("copy.ibt").unwrap();
+///
+/// let mut writer = IbtWriter::new(out, &file.header, &file.vars, file.session_bytes()).unwrap();
+/// for sample in file.samples() {
+///     writer.write_sample(sample.as_bytes()).unwrap();
+/// }
+/// writer.finish(file.disk_sub_header.date).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct IbtWriter<W> {
+    inner: W,
+
+    header: raw::Header,
+    buf_offset: usize,
+    tick_count: Option<usize>,
+    record_count: usize,
+
+    /// Used to compute the disk sub-header
+    session_time_var: Option<VarHeader>,
+    lap_var: Option<VarHeader>,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+    laps: Option<(i32, i32)>,
+}
+
+impl<W: Write + Seek> IbtWriter<W> {
+    /// Start writing a file containing the given vars and session string
+    ///
+    /// Only the `tick_rate`, `session_info_update` and `buf_len` fields of `header` are used, the
+    /// rest are computed from the vars and session string.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a var header cannot be encoded or an IO error occurs.
+    pub fn new(
+        mut inner: W,
+        header: &Header,
+        vars: &VarSet,
+        session_info: &[u8],
+    ) -> Result<Self, IbtWriterError> {
+        let var_headers = vars
+            .all_vars()
+            .map(|var| {
+                var.to_raw()
+                    .map_err(|source| IbtWriterError::InvalidVarHeader {
+                        name: var.name.clone(),
+                        source,
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let var_header_offset = FileHeaders::SIZE;
+        let session_info_offset = var_header_offset + raw::VAR_HEADER_SIZE * var_headers.len();
+        let buf_offset = session_info_offset + session_info.len();
+
+        let raw_header = raw::Header {
+            ver: 2,
+            status: 1,
+            tick_rate: to_c_int(header.tick_rate, "tick_rate")?,
+            session_info_update: to_c_int(header.session_info_update, "session_info_update")?,
+            session_info_len: to_c_int(session_info.len(), "session_info_len")?,
+            session_info_offset: to_c_int(session_info_offset, "session_info_offset")?,
+            num_vars: to_c_int(var_headers.len(), "num_vars")?,
+            var_header_offset: to_c_int(var_header_offset, "var_header_offset")?,
+            num_buf: 1,
+            buf_len: to_c_int(header.buf_len, "buf_len")?,
+            var_bufs: [raw::VarBuf::new(0, 0); 4],
+        };
+        to_c_int(buf_offset, "buf_offset")?;
+
+        // the headers are filled in by `finish`
+        inner.seek(SeekFrom::Start(0))?;
+        inner.write_all(&[0; FileHeaders::SIZE])?;
+        for var_header in &var_headers {
+            inner.write_all(&var_header.to_bytes())?;
+        }
+        inner.write_all(session_info)?;
+
+        Ok(Self {
+            inner,
+            header: raw_header,
+            buf_offset,
+            tick_count: None,
+            record_count: 0,
+            session_time_var: vars.var("SessionTime").cloned(),
+            lap_var: vars.var("Lap").cloned(),
+            start_time: None,
+            end_time: None,
+            laps: None,
+        })
+    }
+
+    /// Append a sample, which must be exactly [`Header::buf_len`] bytes long
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sample has the wrong length or an IO error occurs.
+    pub fn write_sample(&mut self, sample: &[u8]) -> Result<(), IbtWriterError> {
+        let buf_len = self.header.buf_len as usize;
+        if sample.len() != buf_len {
+            return Err(IbtWriterError::InvalidSampleLength {
+                len: sample.len(),
+                buf_len,
+            });
+        }
+
+        self.inner.write_all(sample)?;
+        self.record_count += 1;
+
+        let sample = Sample::new(sample);
+        if let Some(Value::Double(time)) =
+            self.session_time_var.as_ref().map(|v| sample.read_var(v))
+        {
+            self.start_time.get_or_insert(time);
+            self.end_time = Some(time);
+        }
+        if let Some(Value::Int(lap)) = self.lap_var.as_ref().map(|v| sample.read_var(v)) {
+            let (min, max) = self.laps.get_or_insert((lap, lap));
+            *min = lap.min(*min);
+            *max = lap.max(*max);
+        }
+
+        Ok(())
+    }
+
+    /// Number of samples written so far
+    pub fn record_count(&self) -> usize {
+        self.record_count
+    }
+
+    /// Override the tick count stored in the file's variable buffer, which defaults to the number
+    /// of samples written
+    pub fn set_tick_count(&mut self, tick_count: usize) {
+        self.tick_count = Some(tick_count);
+    }
+
+    /// The disk sub-header describing the samples written so far
+    ///
+    /// The start and end times come from the first and last `SessionTime`, and the lap count is
+    /// the number of distinct laps between the lowest and highest `Lap`. Both are zero if the
+    /// vars are missing.
+    pub fn disk_sub_header(&self, date: DateTime<Utc>) -> DiskSubHeader {
+        let secs = |t: Option<f64>| Duration::try_from_secs_f64(t.unwrap_or_default());
+        DiskSubHeader {
+            date,
+            start_time: secs(self.start_time).unwrap_or_default(),
+            end_time: secs(self.end_time).unwrap_or_default(),
+            lap_count: self.lap_count(),
+            record_count: self.record_count,
+        }
+    }
+
+    fn lap_count(&self) -> u32 {
+        self.laps
+            .map_or(0, |(min, max)| max.abs_diff(min).saturating_add(1))
+    }
+
+    /// Fill in the headers, computing the disk sub-header from the samples written, and return
+    /// the underlying writer
+    ///
+    /// See [`IbtWriter::disk_sub_header`] for how the disk sub-header is computed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a value is too large to be stored or an IO error occurs.
+    pub fn finish(self, date: DateTime<Utc>) -> Result<W, IbtWriterError> {
+        let disk_sub_header = raw::DiskSubHeader {
+            start_date: date.timestamp(),
+            start_time: self.start_time.unwrap_or_default(),
+            end_time: self.end_time.unwrap_or_default(),
+            lap_count: to_c_int(self.lap_count(), "lap_count")?,
+            record_count: 0,
+        };
+        self.finish_with(&disk_sub_header)
+    }
+
+    /// Fill in the headers using the given disk sub-header and return the underlying writer
+    ///
+    /// Takes a raw disk sub-header so that existing files can be rewritten exactly, see
+    /// [`IbtFile::raw_disk_sub_header`][crate::IbtFile::raw_disk_sub_header]. Its `record_count`
+    /// is ignored in favor of the number of samples actually written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a value is too large to be stored or an IO error occurs.
+    pub fn finish_with(
+        mut self,
+        disk_sub_header: &raw::DiskSubHeader,
+    ) -> Result<W, IbtWriterError> {
+        let tick_count = self.tick_count.unwrap_or(self.record_count);
+        self.header.var_bufs[0] = raw::VarBuf::new(
+            to_c_int(tick_count, "tick_count")?,
+            to_c_int(self.buf_offset, "buf_offset")?,
+        );
+
+        let raw_sub_header = raw::DiskSubHeader {
+            record_count: to_c_int(self.record_count, "record_count")?,
+            ..*disk_sub_header
+        };
+
+        self.inner.seek(SeekFrom::Start(0))?;
+        self.inner.write_all(&self.header.to_bytes())?;
+        self.inner.write_all(&raw_sub_header.to_bytes())?;
+        self.inner.seek(SeekFrom::End(0))?;
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+}
+
+fn to_c_int<T: TryInto<c_int>>(value: T, field: &'static str) -> Result<c_int, IbtWriterError> {
+    value
+        .try_into()
+        .map_err(|_| IbtWriterError::TooLarge(field))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, time::Duration};
+
+    use claims::{assert_matches, assert_ok};
+
+    use crate::{IbtFile, IbtWriter, IbtWriterError, test_utils::test_ibt};
+
+    fn rewrite(file: &IbtFile, exact: bool) -> Vec<u8> {
+        let out = Cursor::new(Vec::new());
+        let mut writer = assert_ok!(IbtWriter::new(
+            out,
+            &file.header,
+            &file.vars,
+            file.session_bytes()
+        ));
+        for sample in file.samples() {
+            assert_ok!(writer.write_sample(sample.as_bytes()));
+        }
+
+        let out = if exact {
+            writer.set_tick_count(file.var_buf_info.tick_count);
+            assert_ok!(writer.finish_with(&file.raw_disk_sub_header()))
+        } else {
+            assert_ok!(writer.finish(file.disk_sub_header.date))
+        };
+        out.into_inner()
+    }
+
+    #[test]
+    fn round_trips_file() {
+        let bytes = test_ibt(&[1.0, 1.5, 2.0, 2.5]);
+        let file = assert_ok!(IbtFile::from_bytes(&bytes));
+
+        assert_eq!(rewrite(&file, true), bytes);
+    }
+
+    #[test]
+    fn computes_disk_sub_header() {
+        let bytes = test_ibt(&[1.0, 1.5, 2.0, 2.5]);
+        let file = assert_ok!(IbtFile::from_bytes(&bytes));
+
+        let rewritten = assert_ok!(IbtFile::from_bytes(&rewrite(&file, false)));
+        assert_eq!(rewritten.disk_sub_header.start_time, Duration::from_secs(1));
+        assert_eq!(
+            rewritten.disk_sub_header.end_time,
+            Duration::from_secs_f64(2.5)
+        );
+        assert_eq!(rewritten.disk_sub_header.lap_count, 1);
+        assert_eq!(rewritten.disk_sub_header.record_count, 4);
+    }
+
+    #[test]
+    fn rejects_wrong_sample_length() {
+        let file = assert_ok!(IbtFile::from_bytes(&test_ibt(&[1.0])));
+        let mut writer = assert_ok!(IbtWriter::new(
+            Cursor::new(Vec::new()),
+            &file.header,
+            &file.vars,
+            file.session_bytes()
+        ));
+
+        assert_matches!(
+            writer.write_sample(&[0; 3]),
+            Err(IbtWriterError::InvalidSampleLength {
+                len: 3,
+                buf_len: 16
+            })
+        );
+    }
+}
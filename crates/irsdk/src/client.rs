@@ -1,6 +1,7 @@
 use crate::win;
 use crate::win::{TelemetryMemMap, WindowsError};
 use ibt::raw;
+use ibt::session::{SessionInfo, SessionInfoError};
 use ibt::telemetry::{Header, Sample, VarBufInfo, VarHeader, VarSet};
 use itertools::Itertools;
 use std::time::Duration;
@@ -21,8 +22,14 @@ pub enum IRacingClientError {
     #[error(transparent)]
     RawConversionError(#[from] ibt::telemetry::RawConversionError),
 
+    #[error(transparent)]
+    InvalidVarHeader(#[from] ibt::telemetry::VarHeaderError),
+
     #[error(transparent)]
     SignalError(#[from] win::SignalError),
+
+    #[error(transparent)]
+    SessionInfo(#[from] SessionInfoError),
 }
 
 impl From<WindowsError> for IRacingClientError {
@@ -62,7 +69,7 @@ impl IRacingClient {
         let var_headers = raw::VarHeader::slice_from_fraw_bytes(vh_slice)
             .iter()
             .map(VarHeader::from_raw)
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         Ok(Self {
             mem_map,
@@ -138,6 +145,32 @@ impl IRacingClient {
         Ok(Sample::new(buf))
     }
 
+    /// Read the current session string
+    ///
+    /// The session string changes during a session. [`Header::session_info_update`] is
+    /// incremented whenever it does.
+    pub fn raw_session_data(&self) -> Result<String, IRacingClientError> {
+        let header = self.next_header()?;
+
+        // SAFETY:
+        // - We waited on the signal in `self.next_header()`
+        // - Offset and len come from the header
+        // - We copy the data before returning
+        let bytes = unsafe {
+            self.mem_map
+                .as_slice(header.session_info_offset, header.session_info_len)
+        };
+
+        // the string is padded with NULs up to the length of its buffer
+        let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        Ok(String::from_utf8_lossy(&bytes[..len]).into_owned())
+    }
+
+    /// Read and parse the current session string
+    pub fn session_info(&self) -> Result<SessionInfo, IRacingClientError> {
+        Ok(SessionInfo::parse(&self.raw_session_data()?)?)
+    }
+
     pub fn vars(&self) -> &VarSet {
         &self.vars
     }